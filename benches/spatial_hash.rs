@@ -0,0 +1,104 @@
+//! Compares keeping the `WolfGrid` up to date incrementally against rebuilding it every tick,
+//! timing the same simulation tick with and without `WolfConfig::rebuild_grid` on a full 64x64 map
+//! with walls, pillars, doors and bodies, both while the player moves and while nothing moves.
+//!
+//! Run with `cargo bench --bench spatial_hash`.
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use bevy_wolf::*;
+
+const SIZE: usize = 64;
+const TICKS: u32 = 500;
+
+/// Walls around the map and a pillar every 4 tiles, with doors and barrels in between.
+fn map_text() -> String {
+    let mut text = String::from(
+        "[legend]\n# = block body\nD = door body interact\no = sprite body; body_shape = ball; body_radius = 0.4\nP = player body\n[grid]\n",
+    );
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let c = if x == 0 || y == 0 || x == SIZE - 1 || y == SIZE - 1 || (x % 4 == 0 && y % 4 == 0) {
+                '#'
+            } else if x == 1 && y == 1 {
+                'P'
+            } else if x % 8 == 2 && y % 8 == 4 {
+                'D'
+            } else if x % 4 == 2 && y % 4 == 2 {
+                'o'
+            } else {
+                '.'
+            };
+            text.push(c);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn app(rebuild_grid: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin, WolfSimulationPlugin));
+    app.world.resource_mut::<WolfConfig>().rebuild_grid = rebuild_grid;
+    // exactly one simulation tick per update
+    let tick_rate = app.world.resource::<WolfConfig>().tick_rate;
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / tick_rate)));
+
+    let map = parse_text_map(Path::new("maps/bench.wolfmap"), &map_text()).expect("bench map is valid");
+    let handle = app.world.resource_mut::<Assets<WolfMap>>().add(map);
+    app.world.resource_mut::<WolfWorld>().load_map(handle);
+    // load and spawn the map
+    for _ in 0..3 {
+        app.update();
+    }
+    app
+}
+
+fn per_iteration(elapsed: Duration, iterations: u32) -> String {
+    format!("{:>9.3} µs", elapsed.as_secs_f64() * 1e6 / iterations as f64)
+}
+
+/// Times the ticks, where the player either walks along the wall or stands still, having stopped.
+fn tick(app: &mut App, moving: bool) -> Duration {
+    if moving {
+        app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyW);
+    } else {
+        app.world.resource_mut::<ButtonInput<KeyCode>>().release(KeyCode::KeyW);
+        for _ in 0..60 {
+            app.update();
+        }
+    }
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        app.update();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let mut incremental = app(false);
+    let mut rebuild = app(true);
+    let entities = incremental.world.query::<&WolfEntity>().iter(&incremental.world).count();
+    let statics = incremental.world.query::<&WolfStatic>().iter(&incremental.world).count();
+    println!(
+        "{}x{} map, {} entities of which {} static, {} in the grid",
+        SIZE,
+        SIZE,
+        entities,
+        statics,
+        incremental.world.resource::<WolfWorld>().grid.len()
+    );
+
+    for (name, moving) in [("moving", true), ("nothing moving", false)] {
+        println!(
+            "tick, {:<15} incremental grid: {}, rebuilt grid: {}",
+            format!("{},", name),
+            per_iteration(tick(&mut incremental, moving), TICKS),
+            per_iteration(tick(&mut rebuild, moving), TICKS)
+        );
+    }
+}
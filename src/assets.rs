@@ -1,21 +1,56 @@
-use std::io::ErrorKind;
+use std::{io::ErrorKind, path::{Component, Path, PathBuf}};
 
 use array2d::Array2D;
 use bevy::{
-    asset::{AssetLoader, AsyncReadExt, LoadedAsset}, prelude::*, reflect::TypePath, tasks::futures_lite::StreamExt, utils::HashMap
+    asset::{AssetLoader, AsyncReadExt, LoadedAsset, ReadAssetBytesError}, prelude::*, reflect::TypePath, tasks::futures_lite::StreamExt
 };
-use tiled::PropertyValue;
+use thiserror::Error;
+use tiled::{LayerType, PropertyValue};
 
-use crate::WolfEntity;
+use crate::{WolfEntity, WolfObjectShape, WolfShape, COMPONENT_PROPERTY_PREFIX};
 
 #[derive(Asset, TypePath, Debug, Clone)]
 pub struct WolfMap {
     pub layers: Vec<Array2D<Option<WolfEntity>>>,
+    pub objects: Vec<WolfEntity>,
     pub width: u32,
     pub height: u32,
+    /// Properties of the map itself, e.g. a `move_speed` for the whole level. Has no classes.
+    pub properties: WolfEntity,
+}
+
+/// Identifies where in a `WolfMap` an entity was placed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WolfMapKey {
+    Tile {
+        layer:usize,
+        x:u32,
+        y:u32
+    },
+    /// Objects are identified by their object id, or by their index if they do not have one.
+    Object(u32),
+    ObjectIndex(usize)
 }
 
 impl WolfMap {
+    /// Iterates all tiles and objects of the map together with their keys.
+    pub fn entities(&self) -> impl Iterator<Item = (WolfMapKey, &WolfEntity)> + '_ {
+        let tiles = self.layers.iter().enumerate().flat_map(move |(layer_index, layer)| {
+            (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y))).filter_map(move |(x, y)| {
+                layer.get(y as usize, x as usize)
+                    .and_then(|tile| tile.as_ref())
+                    .map(|tile| (WolfMapKey::Tile { layer: layer_index, x, y }, tile))
+            })
+        });
+        let objects = self.objects.iter().enumerate().map(|(index, object)| {
+            match object.object_id {
+                Some(object_id) => (WolfMapKey::Object(object_id), object),
+                None => (WolfMapKey::ObjectIndex(index), object),
+            }
+        });
+        tiles.chain(objects)
+    }
+
     pub fn get(&self, index:UVec2) -> Vec<&WolfEntity> {
         let mut v = Vec::new();
         for layer in self.layers.iter() {
@@ -33,16 +68,315 @@ impl Default for WolfMap {
     fn default() -> Self {
         Self {
             layers: Default::default(),
+            objects: Default::default(),
             width: Default::default(),
             height: Default::default(),
+            properties: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WolfMapLoadError {
+    #[error("could not read map: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse tmx: {0}")]
+    Tmx(#[from] tiled::Error),
+    #[error("could not read dependency: {0}")]
+    ReadAsset(#[from] ReadAssetBytesError),
+    #[error("invalid wolf3d data: {0}")]
+    InvalidWolf3d(String),
+    #[error("line {line}: {reason}")]
+    InvalidText {
+        line: usize,
+        reason: String
+    },
+    #[error("layer '{layer}' has unsupported type '{kind}'")]
+    UnsupportedLayer {
+        layer: String,
+        kind: &'static str
+    },
+    #[error("object {object} in layer '{layer}' has unsupported shape '{kind}'")]
+    UnsupportedObject {
+        layer: String,
+        object: u32,
+        kind: &'static str
+    },
+    #[error("tile {id} at ({x}, {y}) in layer '{layer}' is out of range of tileset '{tileset}'")]
+    TileOutOfRange {
+        layer: String,
+        tileset: String,
+        id: u32,
+        x: u32,
+        y: u32
+    },
+    #[error("property '{property}' must be of type {expected} but was {found}")]
+    InvalidPropertyType {
+        property: String,
+        expected: &'static str,
+        found: &'static str
+    },
+    #[error("body of '{name}' at {pos} is invalid: {reason}")]
+    InvalidBody {
+        name: String,
+        pos: Vec2,
+        reason: String
+    },
+    #[error("trigger '{name}' at {pos} is invalid: {reason}")]
+    InvalidTrigger {
+        name: String,
+        pos: Vec2,
+        reason: String
+    },
+}
+
+/// Properties read by the built-in classes and the type they are expected to have,
+/// where alternative types are separated by `|`.
+const TYPED_PROPERTIES: &[(&str, &str)] = &[
+    ("facing", "float"),
+    ("image", "string|file"),
+    ("atlas_width", "int"),
+    ("atlas_height", "int"),
+    ("body_radius", "float"),
+    ("body_height", "float"),
+    ("body_shape", "int|string"),
+    ("body_half_extent_x", "float"),
+    ("body_half_extent_y", "float"),
+    ("body_half_length", "float"),
+    ("body_axis", "string"),
+    ("body_memberships", "int"),
+    ("body_filter", "int"),
+    ("trigger_filter", "int"),
+    ("player_index", "int"),
+    ("eye_height", "float"),
+    ("move_speed", "float"),
+    ("door_open_time", "float"),
+    ("door_auto_close", "float"),
+    ("push_speed", "float"),
+    ("push_distance", "float"),
+    ("fov", "float"),
+    ("lock", "string"),
+    ("key", "string"),
+];
+
+fn property_type(v: &PropertyValue) -> &'static str {
+    match v {
+        PropertyValue::BoolValue(_) => "bool",
+        PropertyValue::FloatValue(_) => "float",
+        PropertyValue::IntValue(_) => "int",
+        PropertyValue::ColorValue(_) => "color",
+        PropertyValue::StringValue(_) => "string",
+        PropertyValue::FileValue(_) => "file",
+        PropertyValue::ObjectValue(_) => "object",
+    }
+}
+
+/// Returns the type a property read by the built-in classes is expected to have, if any.
+pub(crate) fn expected_property_type(property: &str) -> Option<&'static str> {
+    TYPED_PROPERTIES.iter().find(|(name, _)| *name == property).map(|(_, expected)| *expected)
+}
+
+fn validate_property(property: &str, v: &PropertyValue) -> Result<(), WolfMapLoadError> {
+    let found = property_type(v);
+    if property.starts_with(COMPONENT_PROPERTY_PREFIX) && found != "string" {
+        return Err(WolfMapLoadError::InvalidPropertyType {
+            property: property.to_string(),
+            expected: "string",
+            found,
+        });
+    }
+    if let Some(expected) = expected_property_type(property) {
+        if !expected.split('|').any(|t| t == found) {
+            return Err(WolfMapLoadError::InvalidPropertyType {
+                property: property.to_string(),
+                expected,
+                found,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a path relative to the directory of the map, e.g. `../images/wall.png` relative
+/// to `maps/basic.tmx` becomes `images/wall.png`.
+fn resolve_path(map_path: &Path, file: &str) -> String {
+    let mut resolved = PathBuf::new();
+    for component in map_path.parent().unwrap_or(Path::new("")).join(file).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            },
+            Component::CurDir => {},
+            component => resolved.push(component),
+        }
+    }
+    resolved.to_string_lossy().replace('\\', "/")
+}
+
+/// Creates a `WolfEntity` from a space separated class string and one or more property maps,
+/// where properties found in later maps override those found in earlier maps.
+pub(crate) fn load_entity(
+    map_path: &Path,
+    class: Option<&str>,
+    properties: &[&tiled::Properties],
+    start_pos: Vec3,
+    start_facing: f32,
+    object_shape: WolfObjectShape,
+) -> Result<WolfEntity, WolfMapLoadError> {
+    let mut classes:Vec<String> = Vec::new();
+    for class in class.unwrap_or_default().split_whitespace() {
+        classes.push(class.to_string());
+    }
+
+    let mut wolf_entity = WolfEntity {
+        classes,
+        start_pos,
+        object_shape,
+        ..Default::default()
+    };
+
+    for properties in properties {
+        for (p, v) in properties.iter() {
+            validate_property(p, v)?;
+            match v {
+                PropertyValue::FloatValue(v) => {
+                    wolf_entity.properties_float.insert(p.clone(), *v);
+                },
+                PropertyValue::IntValue(v) => {
+                    wolf_entity.properties_int.insert(p.clone(), *v);
+                },
+                PropertyValue::StringValue(v) => {
+                    wolf_entity.properties_string.insert(p.clone(), v.clone());
+                },
+                PropertyValue::BoolValue(v) => {
+                    wolf_entity.properties_bool.insert(p.clone(), *v);
+                },
+                PropertyValue::ColorValue(v) => {
+                    wolf_entity.properties_color.insert(p.clone(), Color::rgba_u8(v.red, v.green, v.blue, v.alpha));
+                },
+                PropertyValue::FileValue(v) => {
+                    wolf_entity.properties_file.insert(p.clone(), resolve_path(map_path, v));
+                },
+                PropertyValue::ObjectValue(v) => {
+                    // an object reference of 0 means that no object was selected
+                    if *v != 0 {
+                        wolf_entity.properties_entity_ref.insert(p.clone(), *v);
+                    }
+                },
+            };
+        }
+    }
+
+    wolf_entity.start_facing = *wolf_entity.properties_float.get("facing").unwrap_or(&start_facing);
+    if wolf_entity.has_class("body") {
+        if let Err(reason) = WolfShape::from_entity(&wolf_entity) {
+            return Err(WolfMapLoadError::InvalidBody {
+                name: wolf_entity.name.clone(),
+                pos: start_pos.truncate(),
+                reason,
+            });
+        }
+    }
+    if wolf_entity.has_class("trigger") {
+        if let Err(reason) = WolfShape::from_object_shape(&wolf_entity.object_shape) {
+            return Err(WolfMapLoadError::InvalidTrigger {
+                name: wolf_entity.name.clone(),
+                pos: start_pos.truncate(),
+                reason,
+            });
         }
     }
+    Ok(wolf_entity)
+}
+
+/// Creates a free-placed `WolfEntity` from a Tiled object.
+/// 
+/// Tiled places objects in pixels with y pointing down and rotates them clockwise in degrees around their origin,
+/// while the wolf world is measured in tiles with y pointing up and facing counter-clockwise.
+/// Rectangles without a class or a tile are areas, so they become triggers.
+fn load_object(
+    map_path: &Path,
+    layer: &str,
+    object: &tiled::Object,
+    tile_size: Vec2,
+    height: u32,
+) -> Result<WolfEntity, WolfMapLoadError> {
+    let to_world = |p: Vec2| Vec2::new(p.x / tile_size.x, height as f32 - p.y / tile_size.y);
+    let origin = Vec2::new(object.x, object.y);
+    let rotation = Vec2::from_angle(object.rotation.to_radians());
+    let tile = object.get_tile().and_then(|tile| tile.get_tile());
+
+    let (center, shape) = match &object.shape {
+        tiled::ObjectShape::Point(_, _) => (origin, WolfObjectShape::Point),
+        tiled::ObjectShape::Rect { width, height } => {
+            // tile objects have their origin in the bottom left corner, other objects in the top left corner
+            let offset = match object.get_tile() {
+                Some(_) => Vec2::new(*width, -*height) / 2.0,
+                None => Vec2::new(*width, *height) / 2.0,
+            };
+            let center = origin + rotation.rotate(offset);
+            if object.rotation == 0.0 {
+                let half_extents = Vec2::new(*width, *height) / tile_size / 2.0;
+                (center, WolfObjectShape::Rect { half_extents })
+            } else {
+                // rotated rectangles are polygons of their rotated corners
+                let half = Vec2::new(*width, *height) / 2.0;
+                let center_world = to_world(center);
+                let points = [Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0), Vec2::new(-1.0, 1.0)]
+                    .iter()
+                    .map(|corner| to_world(center + rotation.rotate(half * *corner)) - center_world)
+                    .collect();
+                (center, WolfObjectShape::Polygon { points })
+            }
+        },
+        tiled::ObjectShape::Polygon { points } => {
+            let points:Vec<Vec2> = points.iter().map(|(x, y)| origin + rotation.rotate(Vec2::new(*x, *y))).collect();
+            let center = points.iter().copied().sum::<Vec2>() / points.len().max(1) as f32;
+            let center_world = to_world(center);
+            let points = points.iter().map(|p| to_world(*p) - center_world).collect();
+            (center, WolfObjectShape::Polygon { points })
+        },
+        tiled::ObjectShape::Ellipse { .. } => return Err(WolfMapLoadError::UnsupportedObject { layer: layer.to_string(), object: object.id(), kind: "ellipse" }),
+        tiled::ObjectShape::Polyline { .. } => return Err(WolfMapLoadError::UnsupportedObject { layer: layer.to_string(), object: object.id(), kind: "polyline" }),
+        tiled::ObjectShape::Text { .. } => return Err(WolfMapLoadError::UnsupportedObject { layer: layer.to_string(), object: object.id(), kind: "text" }),
+    };
+
+    // the class and properties of the object take precedence over those of its tile
+    let mut class = tile.as_ref().and_then(|tile| tile.user_type.clone()).unwrap_or_default();
+    if !object.user_type.is_empty() {
+        class = object.user_type.clone();
+    }
+    if class.trim().is_empty() && tile.is_none() && matches!(object.shape, tiled::ObjectShape::Rect { .. }) {
+        class = "trigger".to_string();
+    }
+    let mut properties = Vec::new();
+    if let Some(tile) = &tile {
+        properties.push(&tile.properties);
+    }
+    properties.push(&object.properties);
+
+    let mut wolf_entity = load_entity(
+        map_path,
+        Some(&class),
+        &properties,
+        to_world(center).extend(0.5),
+        -object.rotation,
+        shape,
+    ).map_err(|err| match err {
+        WolfMapLoadError::InvalidBody { pos, reason, .. } => WolfMapLoadError::InvalidBody { name: object.name.clone(), pos, reason },
+        WolfMapLoadError::InvalidTrigger { pos, reason, .. } => WolfMapLoadError::InvalidTrigger { name: object.name.clone(), pos, reason },
+        err => err,
+    })?;
+    wolf_entity.name = object.name.clone();
+    wolf_entity.object_id = Some(object.id());
+    Ok(wolf_entity)
 }
 
 #[derive(Default)]
 struct WolfMapAssetLoader;
 
 struct BytesReader<'a> {
+    pub path: &'a std::path::Path,
     pub bytes: &'a [u8],
 }
 impl<'a> tiled::ResourceReader for BytesReader<'a> {
@@ -52,19 +386,105 @@ impl<'a> tiled::ResourceReader for BytesReader<'a> {
 
     fn read_from(
         &mut self,
-        _: &std::path::Path,
+        path: &std::path::Path,
     ) -> std::result::Result<Self::Resource, Self::Error> {
+        // only the map itself is available, external tilesets and templates are not
+        if path != self.path {
+            return Err(std::io::Error::new(ErrorKind::NotFound, format!("external resource '{}' is not supported", path.display())));
+        }
         Ok(self.bytes)
     }
 }
 
+/// Parses a Tiled map, where `path` is the path of the map and is used to resolve file properties.
+///
+/// Tilesets and templates must be embedded in the map.
+pub fn parse_tmx_map(path: &Path, bytes: &[u8]) -> Result<WolfMap, WolfMapLoadError> {
+    let mut loader = tiled::Loader::with_cache_and_reader(
+        tiled::DefaultResourceCache::default(),
+        BytesReader { path, bytes },
+    );
+    let tiled_map = loader.load_tmx_map(path)?;
+    let width = tiled_map.width;
+    let height = tiled_map.height;
+    let tile_size = Vec2::new(tiled_map.tile_width as f32, tiled_map.tile_height as f32);
+    let mut layers = Vec::new();
+    let mut objects = Vec::new();
+    for tiled_layer in tiled_map.layers() {
+        let tiled_tile_layer = match tiled_layer.layer_type() {
+            LayerType::Tiles(tiled_tile_layer) => tiled_tile_layer,
+            LayerType::Objects(tiled_object_layer) => {
+                for object in tiled_object_layer.objects() {
+                    objects.push(load_object(path, &tiled_layer.name, &object, tile_size, height)?);
+                }
+                continue;
+            },
+            LayerType::Image(_) => return Err(WolfMapLoadError::UnsupportedLayer { layer: tiled_layer.name.clone(), kind: "image" }),
+            LayerType::Group(_) => return Err(WolfMapLoadError::UnsupportedLayer { layer: tiled_layer.name.clone(), kind: "group" }),
+        };
+        let mut layer = Array2D::filled_with(None, height as usize, width as usize);
+        for y in 0..height {
+            for x in 0..width {
+                if let Some(tiled_layer_tile) =
+                    tiled_tile_layer.get_tile(x as i32, y as i32)
+                {
+                    let tileset = tiled_layer_tile.get_tileset();
+                    // image collections number their tiles freely, so only defined tiles are in range
+                    let out_of_range = match tileset.image {
+                        Some(_) => tiled_layer_tile.id() >= tileset.tilecount,
+                        None => tiled_layer_tile.get_tile().is_none(),
+                    };
+                    if out_of_range {
+                        return Err(WolfMapLoadError::TileOutOfRange {
+                            layer: tiled_layer.name.clone(),
+                            tileset: tileset.name.clone(),
+                            id: tiled_layer_tile.id(),
+                            x,
+                            y,
+                        });
+                    }
+                    if let Some(tiled_tile) = tiled_layer_tile.get_tile() {
+                        let y = height - y - 1;
+                        let tile = layer.get_mut(y as usize, x as usize).unwrap();
+                        *tile = Some(load_entity(
+                            path,
+                            tiled_tile.user_type.as_deref(),
+                            &[&tiled_tile.properties],
+                            Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.5),
+                            0.0,
+                            WolfObjectShape::Tile,
+                        )?);
+                    }
+                }
+            }
+        }
+        layers.push(layer);
+    }
+
+    let properties = load_entity(
+        path,
+        None,
+        &[&tiled_map.properties],
+        Vec3::ZERO,
+        0.0,
+        WolfObjectShape::Tile,
+    )?;
+    Ok(WolfMap {
+        layers,
+        objects,
+        width,
+        height,
+        properties,
+    })
+}
+
 impl AssetLoader for WolfMapAssetLoader {
 
     type Asset = WolfMap;
     
     type Settings = ();
     
-    type Error = std::io::Error;
+    type Error = WolfMapLoadError;
     
     fn load<'a>(
         &'a self,
@@ -74,81 +494,8 @@ impl AssetLoader for WolfMapAssetLoader {
     ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
-            match reader.read_to_end(&mut bytes).await {
-                Ok(_) => {
-                    let mut loader = tiled::Loader::with_cache_and_reader(
-                        tiled::DefaultResourceCache::default(),
-                        BytesReader { bytes:&bytes },
-                    );
-                    let tiled_map = loader.load_tmx_map(load_context.path()).unwrap();
-                    let width = tiled_map.width;
-                    let height = tiled_map.height;
-                    let mut layers = Vec::new();
-                    for tiled_layer in tiled_map.layers() {
-                        if let Some(tiled_tile_layer) = tiled_layer.as_tile_layer() {
-                            let mut layer = Array2D::filled_with(None, height as usize, width as usize);
-                            for y in 0..height {
-                                for x in 0..width {
-                                    if let Some(tiled_layer_tile) =
-                                        tiled_tile_layer.get_tile(x as i32, y as i32)
-                                    {
-                                        if let Some(tiled_tile) = tiled_layer_tile.get_tile() {
-                                            let y = height - y - 1;
-                                            let tile = layer.get_mut(y as usize, x as usize).unwrap();
-        
-                                            let mut classes:Vec<String> = Vec::new();
-                                            for class in tiled_tile.user_type.clone().unwrap_or_default().split(" ") {
-                                                classes.push(class.to_string());
-                                            }
-        
-                                            let mut properties_float:HashMap<String, f32> = HashMap::new();
-                                            let mut properties_int:HashMap<String, i32> = HashMap::new();
-                                            let mut properties_string:HashMap<String, String> = HashMap::new();
-        
-                                            for (p, v) in tiled_tile.properties.iter() {
-                                                match v {
-                                                    PropertyValue::FloatValue(v) => {
-                                                        properties_float.insert(p.clone(), *v);
-                                                    },
-                                                    PropertyValue::IntValue(v) => {
-                                                        properties_int.insert(p.clone(), *v);
-                                                    },
-                                                    PropertyValue::StringValue(v) => {
-                                                        properties_string.insert(p.clone(), v.clone());
-                                                    },
-                                                    _ => {}
-                                                };
-                                            }
-        
-                                            let facing = *properties_float.get("facing").unwrap_or(&0.0);
-        
-                                            *tile = Some(WolfEntity {
-                                                classes,
-                                                start_pos: Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.5),
-                                                properties_float,
-                                                properties_int,
-                                                properties_string,
-                                                start_facing: facing,
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                            layers.push(layer);
-                        }
-                    }
-        
-                    let wolf_map = WolfMap {
-                        layers,
-                        width,
-                        height,
-                    };
-                    return Ok(wolf_map);
-                },
-                Err(err) => {
-                    return Err(err)
-                },
-            }
+            reader.read_to_end(&mut bytes).await?;
+            parse_tmx_map(load_context.path(), &bytes)
         })
     }
 
@@ -163,3 +510,86 @@ pub fn build_assets(app: &mut App) {
     app.init_asset::<WolfMap>();
     app.init_asset_loader::<WolfMapAssetLoader>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x1 map using an image collection of 2 tiles numbered 0 and 10.
+    fn image_collection_map(tile_ids: &str) -> String {
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="2" height="1" tilewidth="64" tileheight="64" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="entities" tilewidth="64" tileheight="64" tilecount="2" columns="0">
+  <grid orientation="orthogonal" width="1" height="1"/>
+  <tile id="0" type="block body">
+   <image width="64" height="64" source="../images/wall_blue.png"/>
+  </tile>
+  <tile id="10" type="push">
+   <image width="64" height="64" source="../images/pushwall.png"/>
+  </tile>
+ </tileset>
+ <layer id="1" name="walls" width="2" height="1">
+  <data encoding="csv">
+{}
+</data>
+ </layer>
+</map>"#, tile_ids)
+    }
+
+    #[test]
+    fn image_collection_ids_past_tilecount() {
+        let map = parse_tmx_map(Path::new("maps/test.tmx"), image_collection_map("1,11").as_bytes()).unwrap();
+        let tiles = map.get(UVec2::new(1, 0));
+        assert_eq!(tiles.len(), 1);
+        assert!(tiles[0].has_class("push"));
+        assert!(map.get(UVec2::new(0, 0))[0].has_class("block"));
+    }
+
+    #[test]
+    fn image_collection_undefined_id() {
+        let err = parse_tmx_map(Path::new("maps/test.tmx"), image_collection_map("1,6").as_bytes()).unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::TileOutOfRange { id: 5, x: 1, y: 0, .. }), "{}", err);
+    }
+
+    #[test]
+    fn shipped_map() {
+        let bytes = std::fs::read("assets/maps/basic.tmx").unwrap();
+        let map = parse_tmx_map(Path::new("maps/basic.tmx"), &bytes).unwrap();
+        assert_eq!((map.width, map.height), (64, 64));
+        let pushes = map.entities().filter(|(_, we)| we.has_class("push")).count();
+        assert_eq!(pushes, 5);
+    }
+
+    #[test]
+    fn objects() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="64" tileheight="64" infinite="0" nextlayerid="2" nextobjectid="5">
+ <objectgroup id="1" name="objects">
+  <object id="1" name="gate" type=" trigger  key " x="64" y="64" width="128" height="64" rotation="90"/>
+  <object id="2" name="area" type="trigger" x="64" y="64" width="128" height="64"/>
+  <object id="3" name="exit" x="0" y="0" width="64" height="64"/>
+  <object id="4" name="spot" x="32" y="32">
+   <point/>
+  </object>
+ </objectgroup>
+</map>"#;
+        let map = parse_tmx_map(Path::new("maps/test.tmx"), text.as_bytes()).unwrap();
+        let gate = &map.objects[0];
+        assert_eq!(gate.classes, vec!["trigger".to_string(), "key".to_string()]);
+        assert!(gate.start_pos.truncate().distance(Vec2::new(0.5, 2.0)) < 1e-4);
+        let WolfObjectShape::Polygon { points } = gate.object_shape() else {
+            panic!("rotated rectangle is not a polygon");
+        };
+        let max = points.iter().fold(Vec2::ZERO, |max, p| max.max(p.abs()));
+        assert!(max.distance(Vec2::new(0.5, 1.0)) < 1e-4, "{:?}", points);
+        assert!(matches!(WolfShape::from_object_shape(gate.object_shape()), Ok(WolfShape::ConvexPolygon(_))));
+
+        let area = &map.objects[1];
+        assert_eq!(area.object_shape(), &WolfObjectShape::Rect { half_extents: Vec2::new(1.0, 0.5) });
+        assert!(area.start_pos.truncate().distance(Vec2::new(2.0, 2.5)) < 1e-4);
+
+        // rectangles without a class are triggers, other objects keep having no class
+        assert_eq!(map.objects[2].classes, vec!["trigger".to_string()]);
+        assert!(map.objects[3].classes.is_empty());
+    }
+}
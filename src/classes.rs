@@ -0,0 +1,553 @@
+use bevy::{
+    asset::ron,
+    ecs::{reflect::ReflectComponent, system::{Command, SystemId}},
+    prelude::*,
+    reflect::{serde::TypedReflectDeserializer, TypeRegistry},
+};
+use serde::de::DeserializeSeed;
+
+use crate::{
+    Prev, WolfAssets, WolfBody, WolfCamera, WolfCollisionGroups, WolfConfig, WolfController, WolfDoor, WolfEntity, WolfEntityRef, WolfInstance,
+    WolfInteract, WolfInventory, WolfKey, WolfPlayer, WolfPush, WolfShape, WolfSprite, WolfStatic, WolfTileGrid, WolfTrigger, WolfVelocity, WolfViewEffects, WolfWorld,
+};
+
+/// Spawn handlers for the classes of a `WolfEntity`.
+///
+/// A handler is a system taking the spawned entity as input. It is run once for every spawned
+/// entity having the class, after the entity has been given its `Transform`.
+/// A class can have more than one handler, e.g. one for simulation and one for presentation,
+/// and handlers run in the order they were registered.
+#[derive(Resource, Default)]
+pub struct WolfClassRegistry {
+    classes: Vec<(String, SystemId<Entity>)>,
+}
+
+impl WolfClassRegistry {
+    pub fn register(&mut self, class: &str, handler: SystemId<Entity>) {
+        self.classes.push((class.to_string(), handler));
+    }
+
+    /// Removes the handlers of a class, returning them.
+    pub fn remove(&mut self, class: &str) -> Vec<SystemId<Entity>> {
+        let mut removed = Vec::new();
+        self.classes.retain(|(c, handler)| {
+            if c == class {
+                removed.push(*handler);
+                return false;
+            }
+            true
+        });
+        removed
+    }
+
+    pub fn get(&self, class: &str) -> impl Iterator<Item = SystemId<Entity>> + '_ {
+        let class = class.to_string();
+        self.classes
+            .iter()
+            .filter(move |(c, _)| *c == class)
+            .map(|(_, handler)| *handler)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, SystemId<Entity>)> {
+        self.classes
+            .iter()
+            .map(|(class, handler)| (class.as_str(), *handler))
+    }
+}
+
+pub trait WolfClassApp {
+    /// Registers a system to be run for every spawned `WolfEntity` having the class,
+    /// e.g. `app.register_wolf_class("enemy", spawn_enemy)`.
+    ///
+    /// Built-in classes can be replaced by removing them from the `WolfClassRegistry`
+    /// before registering them again.
+    fn register_wolf_class<M>(
+        &mut self,
+        class: &str,
+        handler: impl IntoSystem<Entity, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl WolfClassApp for App {
+    fn register_wolf_class<M>(
+        &mut self,
+        class: &str,
+        handler: impl IntoSystem<Entity, (), M> + 'static,
+    ) -> &mut Self {
+        let id = self.world.register_system(handler);
+        self.world
+            .get_resource_or_insert_with(WolfClassRegistry::default)
+            .register(class, id);
+        self
+    }
+}
+
+/// Prefix of the properties naming a reflected component to insert on a `WolfEntity`,
+/// e.g. `component:Health` = `(max: 100)`.
+pub const COMPONENT_PROPERTY_PREFIX: &str = "component:";
+
+/// Inserts the components named by the `component:` properties of a `WolfEntity`.
+///
+/// The components must be registered in the `AppTypeRegistry` and reflect `Component`,
+/// and the property value is read as RON, where fields left out are taken from the
+/// `Default` of the component if it reflects `Default`. An empty value inserts the default.
+///
+/// The outer braces of `{max: 100}` are read as the parentheses of a struct, but nested
+/// structs must be written with parentheses, e.g. `{range: (min: 1, max: 2)}`, as braces
+/// are maps in RON.
+pub struct InsertWolfComponents {
+    pub entity: Entity,
+}
+
+impl Command for InsertWolfComponents {
+    fn apply(self, world: &mut World) {
+        let Some(we) = world.get::<WolfEntity>(self.entity) else {
+            return;
+        };
+        let components: Vec<(String, String)> = we
+            .properties_string
+            .iter()
+            .filter_map(|(p, v)| {
+                p.strip_prefix(COMPONENT_PROPERTY_PREFIX)
+                    .map(|type_path| (type_path.to_string(), v.clone()))
+            })
+            .collect();
+        if components.is_empty() {
+            return;
+        }
+        let name = we.name.clone();
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        for (type_path, value) in components {
+            match reflect_component(&registry, &type_path, &value) {
+                Ok((reflect_component, component)) => {
+                    let Some(mut entity) = world.get_entity_mut(self.entity) else {
+                        return;
+                    };
+                    reflect_component.insert(&mut entity, component.as_ref(), &registry);
+                }
+                Err(err) => {
+                    warn!("could not insert component '{}' on '{}': {}", type_path, name, err);
+                }
+            }
+        }
+    }
+}
+
+fn reflect_component(
+    registry: &TypeRegistry,
+    type_path: &str,
+    value: &str,
+) -> Result<(ReflectComponent, Box<dyn Reflect>), String> {
+    let Some(registration) = registry
+        .get_with_short_type_path(type_path)
+        .or(registry.get_with_type_path(type_path))
+    else {
+        return Err("type is not registered or its short name is ambiguous".to_string());
+    };
+    let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+        return Err("type does not reflect Component".to_string());
+    };
+
+    // accept `{max: 100}` as well as the RON struct notation `(max: 100)`
+    let value = value.trim();
+    let value = match value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+        Some(fields) => format!("({})", fields),
+        None if value.is_empty() => "()".to_string(),
+        None => value.to_string(),
+    };
+    let mut deserializer = ron::Deserializer::from_str(&value).map_err(|err| err.to_string())?;
+    let component = TypedReflectDeserializer::new(registration, registry)
+        .deserialize(&mut deserializer)
+        .map_err(|err| err.to_string())?;
+    Ok((reflect_component.clone(), component))
+}
+
+/// Makes the entity a `WolfPlayer` moved by a `WolfController`, and spawns a `WolfCamera` following it.
+pub fn spawn_player(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<(&WolfEntity, &Transform)>,
+    world: Res<WolfWorld>,
+    config: Res<WolfConfig>,
+) {
+    let Ok((we, transform)) = entities.get(e) else {
+        return;
+    };
+    let index = we.get_property_int("player_index").map_or(0, |i| (*i).max(0) as usize);
+    commands
+        .entity(e)
+        .insert(WolfPlayer { index })
+        .insert(WolfController {
+            speed: world.property_f32(we, "move_speed").unwrap_or(config.move_speed),
+            ..Default::default()
+        })
+        .insert(WolfVelocity::default())
+        .insert(WolfInventory::default());
+
+    let mut camera = WolfCamera::following(e);
+    if let Some(eye_height) = we.get_property_f32("eye_height") {
+        camera.eye_height = *eye_height;
+    }
+    camera.fov = world.property_f32(we, "fov").unwrap_or(config.fov);
+    let transform = transform.with_translation(transform.translation.truncate().extend(camera.eye_height));
+    commands.spawn((
+        camera,
+        WolfViewEffects::default(),
+        TransformBundle::from_transform(transform),
+        Prev { component: transform },
+    ));
+}
+
+/// The `camera` class of older maps, which spawns a `player` unless the entity already is one.
+pub fn spawn_camera(
+    In(e): In<Entity>,
+    commands: Commands,
+    entities: Query<(&WolfEntity, &Transform)>,
+    world: Res<WolfWorld>,
+    config: Res<WolfConfig>,
+) {
+    let Ok((we, _)) = entities.get(e) else {
+        return;
+    };
+    if !we.has_class("player") {
+        spawn_player(In(e), commands, entities, world, config);
+    }
+}
+
+pub fn spawn_block(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<&WolfEntity>,
+    ass: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: ResMut<WolfAssets>,
+) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    let Some(image) = we.image() else {
+        return;
+    };
+    let block_mesh: Handle<Mesh> = ass.load("meshes/block.gltf#Mesh0/Primitive0");
+    let img_handle = ass.load(image);
+    let material = match assets.standard_materials.get(image) {
+        Some(material) => material,
+        None => {
+            let material = materials.add(StandardMaterial {
+                perceptual_roughness: 1.0,
+                metallic: 0.0,
+                base_color_texture: Some(img_handle),
+                unlit: true,
+                ..Default::default()
+            });
+            assets.standard_materials.insert(image, material.clone());
+            material
+        }
+    };
+
+    commands.entity(e).insert(WolfInstance {
+        mesh: block_mesh,
+        material,
+        request_redraw: true,
+    });
+}
+
+pub fn spawn_sprite(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<&WolfEntity>,
+    ass: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut assets: ResMut<WolfAssets>,
+) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    let atlas_width = *we.get_property_int("atlas_width").unwrap_or(&1) as u8;
+    let atlas_height = *we.get_property_int("atlas_height").unwrap_or(&1) as u8;
+    let atlas = assets
+        .sprite_meshes
+        .get(atlas_height, atlas_width, &mut meshes);
+    commands.entity(e).insert(WolfSprite {
+        atlas_height,
+        atlas_width,
+        ..Default::default()
+    });
+    commands
+        .spawn(PbrBundle {
+            mesh: atlas.index(0),
+            material: materials.add(StandardMaterial {
+                alpha_mode: AlphaMode::Blend,
+                perceptual_roughness: 1.0,
+                metallic: 0.0,
+                cull_mode: None,
+                base_color_texture: we.image().map(|x| ass.load(x)),
+                unlit: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .insert(WolfEntityRef { entity: e });
+}
+
+pub fn spawn_door(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    mut entities: Query<(&WolfEntity, &mut Transform, &mut Prev<Transform>)>,
+    world: Res<WolfWorld>,
+    config: Res<WolfConfig>,
+) {
+    let Ok((we, mut transform, mut prev_transform)) = entities.get_mut(e) else {
+        return;
+    };
+
+    // doors slide along the walls they are placed between
+    let right = we.start_pos.as_uvec3().truncate() + UVec2::X;
+    let tiles = world.map.get(right);
+    for tile in tiles {
+        if tile.has_class("block") {
+            transform.look_to(Vec3::Y, Vec3::Z);
+            prev_transform.component = *transform;
+        }
+    }
+
+    commands.entity(e).insert(WolfDoor {
+        open_time: world.property_f32(we, "door_open_time").unwrap_or(config.door_open_time),
+        auto_close: match world.property_f32(we, "door_auto_close") {
+            Some(secs) => (secs >= 0.0).then_some(secs),
+            None => config.door_auto_close,
+        },
+        lock: we.get_property_string("lock").cloned(),
+        ..Default::default()
+    });
+}
+
+pub fn spawn_door_presentation(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<&WolfEntity>,
+    ass: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut assets: ResMut<WolfAssets>,
+) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    commands.entity(e).with_children(|builder| {
+        builder.spawn(PbrBundle {
+            mesh: assets.sprite_meshes.get(1, 1, &mut meshes).index(0),
+            material: materials.add(StandardMaterial {
+                alpha_mode: AlphaMode::Blend,
+                perceptual_roughness: 1.0,
+                metallic: 0.0,
+                cull_mode: None,
+                base_color_texture: we.image().map(|x| ass.load(x)),
+                unlit: true,
+                ..Default::default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            ..Default::default()
+        });
+    });
+}
+
+pub fn spawn_body(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<&WolfEntity>,
+    world: Res<WolfWorld>,
+) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    let height = we.properties_float.get("body_height").unwrap_or(&1.0);
+    // maps are validated when loaded, so this only fails for entities created elsewhere
+    let shape = WolfShape::from_entity(we).unwrap_or_else(|err| {
+        warn!("invalid body shape of '{}': {}", we.name, err);
+        WolfShape::default()
+    });
+    commands.entity(e).insert(WolfBody {
+        height: *height,
+        shape,
+        groups: WolfCollisionGroups::from_entity(we),
+        ..Default::default()
+    });
+    let tile = we.start_pos.truncate().floor().as_ivec2();
+    if WolfTileGrid::is_static_block(we) && world.tiles.get(tile).is_some() {
+        commands.entity(e).insert(WolfStatic);
+    }
+}
+
+/// Turns the entity into a trigger covering the rectangle or polygon of its object, or else its tile.
+///
+/// Tiled rectangles without a class are given this class when the map is loaded.
+pub fn spawn_trigger(In(e): In<Entity>, mut commands: Commands, entities: Query<&WolfEntity>) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    let shape = WolfShape::from_object_shape(&we.object_shape).unwrap_or_else(|err| {
+        warn!("invalid trigger shape of '{}': {}", we.name, err);
+        WolfShape::default()
+    });
+    let filter = we.properties_int.get("trigger_filter").map_or(u32::MAX, |f| *f as u32);
+    commands.entity(e).insert(WolfTrigger::new(shape, filter));
+}
+
+/// Makes the entity a `WolfKey` named by its `key` property, picked up by walking into it.
+pub fn spawn_key(In(e): In<Entity>, mut commands: Commands, entities: Query<&WolfEntity>) {
+    let Ok(we) = entities.get(e) else {
+        return;
+    };
+    let Some(key) = we.get_property_string("key") else {
+        warn!("key '{}' has no key property", we.name);
+        return;
+    };
+    let mut entity = commands.entity(e);
+    entity.insert(WolfKey { key: key.clone() });
+    // the trigger class sets up the trigger itself, e.g. to filter who can pick it up
+    if !we.has_class("trigger") {
+        let shape = WolfShape::from_object_shape(&we.object_shape).unwrap_or_default();
+        entity.insert(WolfTrigger::new(shape, u32::MAX));
+    }
+}
+
+pub fn spawn_interact(In(e): In<Entity>, mut commands: Commands) {
+    commands.entity(e).insert(WolfInteract {
+        ..Default::default()
+    });
+}
+
+/// Turns the blocks placed in the same tile as the entity into push walls.
+///
+/// `push_speed` and `push_distance` are read from the block, then from the entity and then from the map.
+pub fn spawn_push(
+    In(e): In<Entity>,
+    mut commands: Commands,
+    entities: Query<(Entity, &WolfEntity)>,
+    world: Res<WolfWorld>,
+    config: Res<WolfConfig>,
+) {
+    let Ok((_, we)) = entities.get(e) else {
+        return;
+    };
+    // the settings of the block take precedence over those of the push marker
+    let property = |block:&WolfEntity, property| block.get_property_f32(property).copied().or(world.property_f32(we, property));
+    let push = |block:&WolfEntity| WolfPush {
+        speed: property(block, "push_speed").unwrap_or(config.push_speed),
+        distance: match property(block, "push_distance") {
+            Some(tiles) => (tiles >= 0.0).then_some(tiles),
+            None => config.push_distance,
+        },
+        ..Default::default()
+    };
+    for (other_e, we2) in entities
+        .iter()
+        .filter(|(_, we2)| we2.start_pos == we.start_pos)
+    {
+        if we2.has_class("block") {
+            // push walls move, so they are never part of the tile grid
+            commands
+                .entity(other_e)
+                .insert(WolfInteract::default())
+                .insert(push(we2))
+                .remove::<WolfStatic>();
+        }
+    }
+}
+
+pub fn build_simulation_classes(app: &mut App) {
+    app.init_resource::<WolfClassRegistry>();
+    app.register_wolf_class("player", spawn_player);
+    app.register_wolf_class("camera", spawn_camera);
+    app.register_wolf_class("door", spawn_door);
+    app.register_wolf_class("body", spawn_body);
+    app.register_wolf_class("interact", spawn_interact);
+    app.register_wolf_class("push", spawn_push);
+    app.register_wolf_class("trigger", spawn_trigger);
+    app.register_wolf_class("key", spawn_key);
+}
+
+pub fn build_presentation_classes(app: &mut App) {
+    app.init_resource::<WolfClassRegistry>();
+    app.register_wolf_class("block", spawn_block);
+    app.register_wolf_class("sprite", spawn_sprite);
+    app.register_wolf_class("door", spawn_door_presentation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component, Default)]
+    struct Health {
+        max: i32,
+        current: i32,
+        range: Range,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Default)]
+    struct Range {
+        min: i32,
+        max: i32,
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        {
+            let registry = world.resource::<AppTypeRegistry>();
+            let mut registry = registry.write();
+            registry.register::<Health>();
+            registry.register::<Range>();
+        }
+        world
+    }
+
+    fn insert(world: &mut World, property: &str, value: &str) -> Entity {
+        let mut we = WolfEntity::default();
+        we.properties_string.insert(property.to_string(), value.to_string());
+        let entity = world.spawn(we).id();
+        InsertWolfComponents { entity }.apply(world);
+        entity
+    }
+
+    #[test]
+    fn insert_component() {
+        let mut world = world();
+        let e = insert(&mut world, "component:Health", "{max: 100, range: (min: 1, max: 2)}");
+        let expected = Health { max: 100, current: 0, range: Range { min: 1, max: 2 } };
+        assert_eq!(world.get::<Health>(e), Some(&expected));
+
+        let e = insert(&mut world, "component:Health", "(current: 5)");
+        assert_eq!(world.get::<Health>(e).map(|h| (h.max, h.current)), Some((0, 5)));
+        let e = insert(&mut world, "component:Health", "");
+        assert_eq!(world.get::<Health>(e), Some(&Health::default()));
+        let path = std::any::type_name::<Health>();
+        let e = insert(&mut world, &format!("component:{}", path), "{max: 3}");
+        assert_eq!(world.get::<Health>(e).map(|h| h.max), Some(3));
+    }
+
+    #[test]
+    fn insert_invalid() {
+        let mut world = world();
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let err = reflect_component(&registry, "Armor", "{}").err().unwrap();
+        assert!(err.contains("not registered"), "{}", err);
+        let err = reflect_component(&registry, "Range", "{}").err().unwrap();
+        assert!(err.contains("does not reflect Component"), "{}", err);
+        assert!(reflect_component(&registry, "Health", "{max: }").is_err());
+        // nested braces are a map, not a struct
+        assert!(reflect_component(&registry, "Health", "{range: {min: 1, max: 2}}").is_err());
+        drop(registry);
+
+        let e = insert(&mut world, "component:Health", "{max: 100");
+        assert!(world.get::<Health>(e).is_none());
+    }
+}
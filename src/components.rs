@@ -31,30 +31,279 @@ impl<T:Component + Clone> DerefMut for Prev<T> {
     }
 }
 
-pub const BODY_SHAPE_CUBOID:u8 = 0;
-pub const BODY_SHAPE_BALL:u8 = 1;
+/// The footprint a `WolfBody` collides with, centered on the entity.
+#[derive(Clone, Debug)]
+pub enum WolfShape {
+    Cuboid(parry2d::shape::Cuboid),
+    Ball(parry2d::shape::Ball),
+    Capsule(parry2d::shape::Capsule),
+    ConvexPolygon(parry2d::shape::ConvexPolygon)
+}
+
+impl WolfShape {
+    pub fn cuboid(half_extents:Vec2) -> Self {
+        Self::Cuboid(parry2d::shape::Cuboid::new([half_extents.x, half_extents.y].into()))
+    }
+
+    pub fn ball(radius:f32) -> Self {
+        Self::Ball(parry2d::shape::Ball::new(radius))
+    }
+
+    /// A capsule around the segment from `a` to `b`.
+    pub fn capsule(a:Vec2, b:Vec2, radius:f32) -> Self {
+        Self::Capsule(parry2d::shape::Capsule::new([a.x, a.y].into(), [b.x, b.y].into(), radius))
+    }
+
+    /// Returns `None` if the points do not form a convex polygon.
+    pub fn convex_polygon(points:&[Vec2]) -> Option<Self> {
+        let points:Vec<_> = points.iter().map(|p| [p.x, p.y].into()).collect();
+        let polygon = parry2d::shape::ConvexPolygon::from_convex_hull(&points)?;
+        // the hull drops the points making the polygon concave
+        if polygon.points().len() != points.len() {
+            return None;
+        }
+        Some(Self::ConvexPolygon(polygon))
+    }
+
+    pub fn as_shape(&self) -> &dyn parry2d::shape::Shape {
+        match self {
+            WolfShape::Cuboid(s) => s,
+            WolfShape::Ball(s) => s,
+            WolfShape::Capsule(s) => s,
+            WolfShape::ConvexPolygon(s) => s
+        }
+    }
+
+    /// Reads the shape from the `body_*` properties of an entity.
+    ///
+    /// `body_shape` is one of `cuboid`, `ball`, `capsule` or `polygon`, or for older maps
+    /// `0` for a cuboid and `1` for a ball. Without it, rectangle objects are cuboids of their
+    /// size and polygon objects are polygons of their points, while anything else is a cuboid.
+    /// Cuboids use `body_half_extent_x` and `body_half_extent_y`, balls and capsules use
+    /// `body_radius`, and capsules use `body_half_length` along `body_axis`, `x` or `y`.
+    pub fn from_entity(we:&WolfEntity) -> Result<Self, String> {
+        let radius = *we.get_property_f32("body_radius").unwrap_or(&0.5);
+        let shape = match (we.get_property_string("body_shape"), we.get_property_int("body_shape")) {
+            (Some(shape), _) => shape.as_str(),
+            (None, Some(0)) => "cuboid",
+            (None, Some(1)) => "ball",
+            (None, Some(shape)) => return Err(format!("unknown body_shape {}", shape)),
+            (None, None) => match we.object_shape {
+                WolfObjectShape::Polygon { .. } => "polygon",
+                _ => "cuboid"
+            }
+        };
+        if radius <= 0.0 {
+            return Err("body_radius must be positive".to_string());
+        }
+
+        match shape {
+            "cuboid" => {
+                let default = match we.object_shape {
+                    WolfObjectShape::Rect { half_extents } => half_extents,
+                    _ => Vec2::splat(radius)
+                };
+                let half_extents = Vec2::new(
+                    *we.get_property_f32("body_half_extent_x").unwrap_or(&default.x),
+                    *we.get_property_f32("body_half_extent_y").unwrap_or(&default.y),
+                );
+                if half_extents.x <= 0.0 || half_extents.y <= 0.0 {
+                    return Err("body half extents must be positive".to_string());
+                }
+                Ok(Self::cuboid(half_extents))
+            },
+            "ball" => Ok(Self::ball(radius)),
+            "capsule" => {
+                let half_length = *we.get_property_f32("body_half_length").unwrap_or(&0.5);
+                if half_length < 0.0 {
+                    return Err("body_half_length must not be negative".to_string());
+                }
+                let axis = match we.get_property_string("body_axis").map(|a| a.as_str()) {
+                    Some("x") => Vec2::X,
+                    Some("y") | None => Vec2::Y,
+                    Some(axis) => return Err(format!("unknown body_axis '{}'", axis))
+                };
+                Ok(Self::capsule(-axis * half_length, axis * half_length, radius))
+            },
+            "polygon" => {
+                let WolfObjectShape::Polygon { points } = &we.object_shape else {
+                    return Err("body_shape polygon requires a polygon object".to_string());
+                };
+                Self::convex_polygon(points).ok_or_else(|| "body polygon must be convex".to_string())
+            },
+            shape => Err(format!("unknown body_shape '{}'", shape))
+        }
+    }
+}
+
+impl WolfShape {
+    /// The shape covering the footprint of an entity as it was placed in the map,
+    /// where points are covered by a ball of half a tile.
+    pub fn from_object_shape(shape:&WolfObjectShape) -> Result<Self, String> {
+        match shape {
+            WolfObjectShape::Tile => Ok(Self::default()),
+            WolfObjectShape::Point => Ok(Self::ball(0.5)),
+            WolfObjectShape::Rect { half_extents } => Ok(Self::cuboid(*half_extents)),
+            WolfObjectShape::Polygon { points } => Self::convex_polygon(points).ok_or_else(|| "polygon must be convex".to_string())
+        }
+    }
+}
+
+impl Default for WolfShape {
+    fn default() -> Self {
+        Self::cuboid(Vec2::splat(0.5))
+    }
+}
+
+/// Collision groups as bitmasks, one group per bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WolfCollisionGroups {
+    /// The groups the body is a member of.
+    pub memberships:u32,
+    /// The groups the body collides with.
+    pub filter:u32
+}
+
+impl WolfCollisionGroups {
+    pub fn new(memberships:u32, filter:u32) -> Self {
+        Self { memberships, filter }
+    }
+
+    /// Two bodies collide if each is a member of a group the other collides with.
+    pub fn test(&self, other:&WolfCollisionGroups) -> bool {
+        self.memberships & other.filter != 0 && other.memberships & self.filter != 0
+    }
+}
+
+impl WolfCollisionGroups {
+    /// Reads the groups from the `body_memberships` and `body_filter` properties of an entity,
+    /// where the masks are the bits of an int, so -1 is every group.
+    pub fn from_entity(we:&WolfEntity) -> Self {
+        let memberships = we.get_property_int("body_memberships").map_or(u32::MAX, |m| *m as u32);
+        let filter = we.get_property_int("body_filter").map_or(u32::MAX, |f| *f as u32);
+        Self::new(memberships, filter)
+    }
+}
+
+impl Default for WolfCollisionGroups {
+    fn default() -> Self {
+        Self::new(u32::MAX, u32::MAX)
+    }
+}
+
+/// Marks a body that is part of the `WolfTileGrid` of the map instead of the spatial hash.
+#[derive(Component, Clone, Copy, Default)]
+pub struct WolfStatic;
 
 #[derive(Component, Clone, Debug)]
 pub struct WolfBody {
     pub height:f32,
-    pub radius:f32,
-    pub shape:u8,
-    pub disabled:bool
+    pub shape:WolfShape,
+    pub disabled:bool,
+    pub groups:WolfCollisionGroups
+}
+
+impl WolfBody {
+    pub fn collides_with(&self, other:&WolfBody) -> bool {
+        self.groups.test(&other.groups)
+    }
 }
 
 impl Default for WolfBody {
     fn default() -> Self {
         Self { 
             height:1.0,
-            radius:0.5,
-            shape:BODY_SHAPE_CUBOID,
-            disabled: false
+            shape:WolfShape::default(),
+            disabled: false,
+            groups: WolfCollisionGroups::default()
         }
     }
 }
 
-#[derive(Component, Clone, Default)]
+/// Velocity in tiles per second, applied by the `WolfController` of the entity.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct WolfVelocity {
+    pub linear:Vec2
+}
+
+/// Moves an entity by its `WolfVelocity`, sliding along whatever its body collides with.
+#[derive(Component, Clone, Debug)]
+pub struct WolfController {
+    /// The direction to move in, where a length of 1 is walking at `speed` and running is longer.
+    pub wish:Vec2,
+    /// Walking speed in tiles per second.
+    pub speed:f32,
+    /// How fast the velocity approaches the wished velocity, in tiles per second squared.
+    pub acceleration:f32,
+    /// How fast the velocity slows down when not wishing to move, in tiles per second squared.
+    pub friction:f32,
+    /// The part of the motion of the last tick that was blocked by collisions.
+    pub remaining:Vec2
+}
+
+impl Default for WolfController {
+    fn default() -> Self {
+        Self {
+            wish:Vec2::ZERO,
+            speed:10.0,
+            acceleration:100.0,
+            friction:100.0,
+            remaining:Vec2::ZERO
+        }
+    }
+}
+
+/// Effects on the view of a `WolfCamera` following a target, applied on top of the eyes of
+/// the target by the presentation, so the `Transform` of the camera is left untouched.
+#[derive(Component, Clone, Default, Debug)]
+pub struct WolfViewEffects {
+    /// How far the target has walked, in tiles.
+    pub bob_phase:f32,
+    /// Height of the head bob, following the speed of the target.
+    pub bob:f32,
+    /// In radians, positive to the right.
+    pub roll:f32,
+    /// In radians, positive up.
+    pub flinch:f32,
+    /// How strongly the view shakes, from 0 to 1.
+    pub trauma:f32
+}
+
+/// An entity moved and turned by the input of a local player.
+#[derive(Component, Clone, Default, Debug)]
+pub struct WolfPlayer {
+    /// Which local player it is, where the keyboard and mouse drive player 0.
+    pub index:usize
+}
+
+/// Views the world from its own entity, following a target if it has one.
+#[derive(Component, Clone, Debug)]
 pub struct WolfCamera {
+    /// The entity to look out of, e.g. a `WolfPlayer`. Without one the camera stays where it is.
+    pub target:Option<Entity>,
+    /// The height of the eyes above the floor when following the target.
+    pub eye_height:f32,
+    /// Vertical field of view in degrees.
+    pub fov:f32
+}
+
+impl WolfCamera {
+    pub fn following(target:Entity) -> Self {
+        Self {
+            target:Some(target),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for WolfCamera {
+    fn default() -> Self {
+        Self {
+            target:None,
+            eye_height:0.5,
+            fov:45.0
+        }
+    }
 }
 
 #[derive(Component, Default, Clone)]
@@ -69,14 +318,38 @@ pub struct WolfEntityRef {
     pub entity:Entity
 }
 
-#[derive(Component, Default, Clone, Debug)]
+/// The footprint of a `WolfEntity` as it was placed in the map.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub enum WolfObjectShape {
+    /// Fills the tile it was placed in.
+    #[default]
+    Tile,
+    Point,
+    /// An axis aligned rectangle, as rotated rectangles are placed as polygons.
+    Rect {
+        half_extents:Vec2
+    },
+    /// Points are relative to the start position of the entity.
+    Polygon {
+        points:Vec<Vec2>
+    }
+}
+
+#[derive(Component, Default, Clone, Debug, PartialEq)]
 pub struct WolfEntity {
+    pub(crate) name: String,
     pub(crate) classes: Vec<String>,
     pub(crate) properties_float: HashMap<String, f32>,
     pub(crate) properties_int: HashMap<String, i32>,
     pub(crate) properties_string: HashMap<String, String>,
+    pub(crate) properties_bool: HashMap<String, bool>,
+    pub(crate) properties_color: HashMap<String, Color>,
+    pub(crate) properties_file: HashMap<String, String>,
+    pub(crate) properties_entity_ref: HashMap<String, u32>,
+    pub(crate) object_id: Option<u32>,
     pub(crate) start_pos: Vec3,
-    pub(crate) start_facing: f32
+    pub(crate) start_facing: f32,
+    pub(crate) object_shape: WolfObjectShape
 }
 
 impl WolfEntity {
@@ -84,10 +357,19 @@ impl WolfEntity {
         self.start_pos
     }
 
+    /// Facing in degrees, counter-clockwise from the x-axis.
     pub fn start_facing(&self) -> f32 {
         self.start_facing
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn object_shape(&self) -> &WolfObjectShape {
+        &self.object_shape
+    }
+
     pub fn has_class(&self, class: &str) -> bool {
         for c in self.classes.iter() {
             if c == class {
@@ -109,6 +391,34 @@ impl WolfEntity {
     pub fn get_property_string(&self, property:&str) -> Option<&String> {
         self.properties_string.get(property)
     }
+
+    /// The `image` property, given either as a string or as a file.
+    pub fn image(&self) -> Option<&String> {
+        self.get_property_string("image").or(self.get_property_file("image"))
+    }
+
+    pub fn get_property_bool(&self, property:&str) -> Option<&bool> {
+        self.properties_bool.get(property)
+    }
+
+    pub fn get_property_color(&self, property:&str) -> Option<&Color> {
+        self.properties_color.get(property)
+    }
+
+    /// Path of a file property, relative to the asset folder rather than the map.
+    pub fn get_property_file(&self, property:&str) -> Option<&String> {
+        self.properties_file.get(property)
+    }
+
+    /// Id of the map object referenced by an object property, see `WolfWorld::object_entity`.
+    pub fn get_property_entity_ref(&self, property:&str) -> Option<&u32> {
+        self.properties_entity_ref.get(property)
+    }
+
+    /// Id of the object this entity was created from, if it was placed as an object.
+    pub fn object_id(&self) -> Option<u32> {
+        self.object_id
+    }
 }
 
 #[derive(Component, Default, Clone)]
@@ -145,12 +455,61 @@ pub struct WolfInstanceManager<M:Material + Asset> {
 #[derive(Component)]
 pub struct WolfUIFPSText;
 
+/// Marks the floor and ceiling spawned for the current map.
+#[derive(Component)]
+pub struct WolfMapGeometry;
+
 
 #[derive(Component, Default)]
 pub struct WolfInteract {
     
 }
 
+/// An area sending `WolfTriggerEnter` and `WolfTriggerExit` as bodies start and stop overlapping it.
+#[derive(Component, Clone, Debug, Default)]
+pub struct WolfTrigger {
+    pub shape:WolfShape,
+    /// Only bodies that are members of one of these groups trigger it.
+    pub filter:u32,
+    pub(crate) inside:Vec<Entity>
+}
+
+impl WolfTrigger {
+    pub fn new(shape:WolfShape, filter:u32) -> Self {
+        Self { shape, filter, inside: Vec::new() }
+    }
+
+    /// The bodies currently inside the trigger, in the order they entered.
+    pub fn inside(&self) -> &[Entity] {
+        &self.inside
+    }
+}
+
+/// The keys an entity carries, opening the `WolfDoor`s locked with them.
+#[derive(Component, Clone, Debug, Default)]
+pub struct WolfInventory {
+    pub keys:Vec<String>
+}
+
+impl WolfInventory {
+    pub fn has_key(&self, key:&str) -> bool {
+        self.keys.iter().any(|k| k == key)
+    }
+
+    /// Adds the key unless it is carried already.
+    pub fn add_key(&mut self, key:&str) {
+        if !self.has_key(key) {
+            self.keys.push(key.to_string());
+        }
+    }
+}
+
+/// A key picked up by the first entity with a `WolfInventory` entering its `WolfTrigger`.
+#[derive(Component, Clone, Debug)]
+pub struct WolfKey {
+    pub key:String
+}
+
 #[derive(Default, Clone)]
 pub struct Timer {
     pub current:f32,
@@ -203,19 +562,65 @@ impl Default for DoorState {
     }
 }
 
-#[derive(Component, Default)]
+#[derive(Component)]
 pub struct WolfDoor {
     pub pos:Vec3,
-    pub state:DoorState
+    pub state:DoorState,
+    /// Seconds to open or close.
+    pub open_time:f32,
+    /// Seconds to stay open before closing, or `None` to stay open.
+    pub auto_close:Option<f32>,
+    /// The key needed to open it, e.g. `gold`, or `None` if anyone can.
+    pub lock:Option<String>,
+    /// How far the door had slid open at the start of the tick, for interpolating the slide.
+    pub(crate) prev_slide:f32
+}
+
+impl Default for WolfDoor {
+    fn default() -> Self {
+        Self {
+            pos:Vec3::ZERO,
+            state:DoorState::Closed,
+            open_time:0.5,
+            auto_close:Some(3.0),
+            lock:None,
+            prev_slide:0.0
+        }
+    }
 }
 
 impl WolfDoor {
-   
+    /// How far the door has slid open, from 0 when closed to 1 when open.
+    pub fn slide(&self) -> f32 {
+        match &self.state {
+            DoorState::Closed => 0.0,
+            DoorState::Closing { closing } => 1.0 - closing.alpha(),
+            DoorState::Opening { opening } => opening.alpha(),
+            DoorState::Open { .. } => 1.0
+        }
+    }
 }
 
-#[derive(Component, Default)]
+#[derive(Component)]
 pub struct WolfPush {
-    pub vel:Vec3
+    pub vel:Vec3,
+    /// In tiles per second.
+    pub speed:f32,
+    /// How many tiles to move, or `None` to move until blocked.
+    pub distance:Option<f32>,
+    /// How many tiles it has moved.
+    pub moved:f32
+}
+
+impl Default for WolfPush {
+    fn default() -> Self {
+        Self {
+            vel:Vec3::ZERO,
+            speed:1.0,
+            distance:None,
+            moved:0.0
+        }
+    }
 }
 
 pub const NORTH:usize = 0;
@@ -234,3 +639,113 @@ pub struct WolfSouth;
 pub struct WolfWest;
 #[derive(Component, Default)]
 pub struct WolfEast;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(object_shape:WolfObjectShape, floats:&[(&str, f32)], strings:&[(&str, &str)]) -> WolfEntity {
+        WolfEntity {
+            properties_float: floats.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            properties_string: strings.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            object_shape,
+            ..Default::default()
+        }
+    }
+
+    fn half_extents(shape:&WolfShape) -> Vec2 {
+        let WolfShape::Cuboid(cuboid) = shape else {
+            panic!("{:?} is not a cuboid", shape);
+        };
+        Vec2::new(cuboid.half_extents.x, cuboid.half_extents.y)
+    }
+
+    #[test]
+    fn collision_groups() {
+        let all = WolfCollisionGroups::default();
+        assert!(all.test(&all));
+        // each body has to collide with a group of the other
+        let a = WolfCollisionGroups::new(0b01, 0b10);
+        let b = WolfCollisionGroups::new(0b10, 0b01);
+        assert!(a.test(&b) && b.test(&a));
+        assert!(!a.test(&a));
+        assert!(!a.test(&WolfCollisionGroups::new(0b10, 0b10)));
+        assert!(!all.test(&WolfCollisionGroups::new(u32::MAX, 0)));
+        assert!(!all.test(&WolfCollisionGroups::new(0, u32::MAX)));
+
+        let mut we = entity(WolfObjectShape::Tile, &[], &[]);
+        we.properties_int.insert("body_memberships".to_string(), 0b100);
+        we.properties_int.insert("body_filter".to_string(), -1);
+        assert_eq!(WolfCollisionGroups::from_entity(&we), WolfCollisionGroups::new(0b100, u32::MAX));
+        let body = WolfBody { groups: WolfCollisionGroups::from_entity(&we), ..Default::default() };
+        assert!(!body.collides_with(&WolfBody { groups: a, ..Default::default() }));
+        assert!(body.collides_with(&WolfBody::default()));
+    }
+
+    #[test]
+    fn shape_cuboid() {
+        let we = entity(WolfObjectShape::Tile, &[], &[]);
+        assert_eq!(half_extents(&WolfShape::from_entity(&we).unwrap()), Vec2::splat(0.5));
+        let we = entity(WolfObjectShape::Tile, &[("body_half_extent_x", 0.25)], &[]);
+        assert_eq!(half_extents(&WolfShape::from_entity(&we).unwrap()), Vec2::new(0.25, 0.5));
+        let we = entity(WolfObjectShape::Rect { half_extents: Vec2::new(1.0, 2.0) }, &[], &[]);
+        assert_eq!(half_extents(&WolfShape::from_entity(&we).unwrap()), Vec2::new(1.0, 2.0));
+        let we = entity(WolfObjectShape::Point, &[("body_radius", 0.3)], &[("body_shape", "cuboid")]);
+        assert_eq!(half_extents(&WolfShape::from_entity(&we).unwrap()), Vec2::splat(0.3));
+    }
+
+    #[test]
+    fn shape_ball_and_capsule() {
+        let we = entity(WolfObjectShape::Tile, &[("body_radius", 0.4)], &[("body_shape", "ball")]);
+        assert!(matches!(WolfShape::from_entity(&we), Ok(WolfShape::Ball(ball)) if ball.radius == 0.4));
+        let mut we = entity(WolfObjectShape::Tile, &[], &[]);
+        we.properties_int.insert("body_shape".to_string(), 1);
+        assert!(matches!(WolfShape::from_entity(&we), Ok(WolfShape::Ball(ball)) if ball.radius == 0.5));
+        we.properties_int.insert("body_shape".to_string(), 0);
+        assert!(matches!(WolfShape::from_entity(&we), Ok(WolfShape::Cuboid(_))));
+
+        let we = entity(WolfObjectShape::Tile, &[("body_half_length", 0.2)], &[("body_shape", "capsule"), ("body_axis", "x")]);
+        let Ok(WolfShape::Capsule(capsule)) = WolfShape::from_entity(&we) else {
+            panic!("not a capsule");
+        };
+        assert_eq!((capsule.segment.a.x, capsule.segment.b.x, capsule.segment.a.y), (-0.2, 0.2, 0.0));
+        assert_eq!(capsule.radius, 0.5);
+    }
+
+    #[test]
+    fn shape_polygon() {
+        let triangle = vec![Vec2::new(-0.5, -0.5), Vec2::new(0.5, -0.5), Vec2::new(0.0, 0.5)];
+        let we = entity(WolfObjectShape::Polygon { points: triangle.clone() }, &[], &[]);
+        assert!(matches!(WolfShape::from_entity(&we), Ok(WolfShape::ConvexPolygon(polygon)) if polygon.points().len() == 3));
+
+        let concave = vec![Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(0.0, -0.5), Vec2::new(0.0, 1.0)];
+        let we = entity(WolfObjectShape::Polygon { points: concave }, &[], &[]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let degenerate = vec![Vec2::new(-1.0, 0.0), Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)];
+        let we = entity(WolfObjectShape::Polygon { points: degenerate }, &[], &[]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[], &[("body_shape", "polygon")]);
+        assert!(WolfShape::from_entity(&we).is_err());
+    }
+
+    #[test]
+    fn shape_invalid() {
+        let we = entity(WolfObjectShape::Tile, &[], &[("body_shape", "cone")]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let mut we = entity(WolfObjectShape::Tile, &[], &[]);
+        we.properties_int.insert("body_shape".to_string(), 7);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[("body_radius", 0.0)], &[("body_shape", "ball")]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[("body_radius", -1.0)], &[]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[("body_half_extent_y", 0.0)], &[]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Rect { half_extents: Vec2::new(1.0, -1.0) }, &[], &[]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[("body_half_length", -0.1)], &[("body_shape", "capsule")]);
+        assert!(WolfShape::from_entity(&we).is_err());
+        let we = entity(WolfObjectShape::Tile, &[], &[("body_shape", "capsule"), ("body_axis", "z")]);
+        assert!(WolfShape::from_entity(&we).is_err());
+    }
+}
@@ -6,6 +6,77 @@ pub struct WolfInteractEvent {
     pub entity:Entity
 }
 
+/// Sent when a body `a` moves into another body or static block `b`, either resolved by `body_system`
+/// or hit by a `WolfController` moving it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WolfCollisionEvent {
+    pub a:Entity,
+    pub b:Entity,
+    /// Points from `a` towards `b`.
+    pub normal:Vec2,
+    /// How far `a` overlapped `b`, or for controlled bodies, which stop short of what they hit,
+    /// how far they would have moved into it.
+    pub depth:f32
+}
+
+/// Sent when a body starts overlapping a `WolfTrigger`.
+#[derive(Event)]
+pub struct WolfTriggerEnter {
+    pub trigger:Entity,
+    pub entity:Entity
+}
+
+/// Sent when a body stops overlapping a `WolfTrigger`, including when it was despawned or disabled.
+#[derive(Event)]
+pub struct WolfTriggerExit {
+    pub trigger:Entity,
+    pub entity:Entity
+}
+
+/// Sent when an interactor without the key tries to open a locked `WolfDoor`.
+#[derive(Event, Clone, Debug)]
+pub struct WolfDoorDeniedEvent {
+    pub door:Entity,
+    pub interactor:Entity,
+    pub lock:String
+}
+
+/// Sent when an entity picks up a `WolfKey`, which is despawned.
+#[derive(Event, Clone, Debug)]
+pub struct WolfKeyPickupEvent {
+    pub entity:Entity,
+    pub key:String
+}
+
+/// Kicks the view of the cameras following `entity` up, e.g. when it takes damage.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WolfFlinchEvent {
+    pub entity:Entity,
+    /// Where 1 kicks the view by the `flinch` of the `WolfConfig`.
+    pub amount:f32
+}
+
+/// Shakes the view of the cameras near a position, e.g. of an explosion.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WolfShakeEvent {
+    pub position:Vec2,
+    /// From 0 to 1 at the position, fading to 0 at the radius.
+    pub strength:f32,
+    pub radius:f32
+}
+
+/// Sent when a map has been loaded and its entities spawned, but not when it is hot-reloaded.
+#[derive(Event)]
+pub struct WolfMapLoadedEvent;
+
 pub fn build_events(app:&mut App) {
     app.add_event::<WolfInteractEvent>();
+    app.add_event::<WolfCollisionEvent>();
+    app.add_event::<WolfMapLoadedEvent>();
+    app.add_event::<WolfTriggerEnter>();
+    app.add_event::<WolfTriggerExit>();
+    app.add_event::<WolfDoorDeniedEvent>();
+    app.add_event::<WolfKeyPickupEvent>();
+    app.add_event::<WolfFlinchEvent>();
+    app.add_event::<WolfShakeEvent>();
 }
\ No newline at end of file
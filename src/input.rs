@@ -0,0 +1,264 @@
+use std::path::Path;
+
+use bevy::{asset::ron, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Something a player can do, bound to inputs by the `WolfInputMap`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WolfAction {
+    /// Positive is forward.
+    MoveForward,
+    /// Positive is to the right.
+    Strafe,
+    /// Positive is to the right.
+    Turn,
+    /// Positive is up.
+    Look,
+    /// While held, turning with held inputs strafes instead.
+    StrafeModifier,
+    Use,
+    Fire,
+    Run,
+    NextWeapon,
+    PreviousWeapon,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum WolfInputSource {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    /// Horizontal mouse movement in pixels, positive to the right.
+    MouseX,
+    /// Vertical mouse movement in pixels, positive downwards.
+    MouseY,
+    GamepadButton(GamepadButtonType),
+    GamepadAxis(GamepadAxisType),
+}
+
+impl WolfInputSource {
+    /// Whether the source is moved rather than held, adding to the motion of its action
+    /// instead of the value.
+    pub fn is_motion(&self) -> bool {
+        matches!(self, WolfInputSource::MouseX | WolfInputSource::MouseY)
+    }
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WolfBinding {
+    pub action: WolfAction,
+    pub source: WolfInputSource,
+    /// Multiplies the value of the source, e.g. -1 for the key walking backwards.
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+#[derive(Debug, Error)]
+pub enum WolfInputMapError {
+    #[error("could not read input map: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse input map: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write input map: {0}")]
+    Write(#[from] ron::Error),
+}
+
+/// Binds keys, mouse buttons, mouse movement and gamepad buttons and axes to the `WolfAction`s.
+///
+/// The keyboard and mouse drive player 0 together with the first gamepad, and every further
+/// gamepad, in the order of their ids, drives the next player. The bindings can be changed at any time, and read from and
+/// written to RON, e.g.
+///
+/// ```text
+/// (bindings: [
+///     (action: MoveForward, source: Key(KeyW)),
+///     (action: MoveForward, source: Key(KeyS), scale: -1.0),
+///     (action: Use, source: GamepadButton(South)),
+/// ])
+/// ```
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WolfInputMap {
+    pub bindings: Vec<WolfBinding>,
+}
+
+impl Default for WolfInputMap {
+    fn default() -> Self {
+        use WolfAction as A;
+        use WolfInputSource as S;
+        let mut map = Self { bindings: Vec::new() };
+        map.bind(A::MoveForward, S::Key(KeyCode::KeyW), 1.0)
+            .bind(A::MoveForward, S::Key(KeyCode::KeyS), -1.0)
+            .bind(A::Strafe, S::Key(KeyCode::KeyA), -1.0)
+            .bind(A::Strafe, S::Key(KeyCode::KeyD), 1.0)
+            .bind(A::Turn, S::MouseX, 1.0)
+            .bind(A::Look, S::MouseY, -1.0)
+            .bind(A::Use, S::Key(KeyCode::Space), 1.0)
+            .bind(A::Fire, S::Key(KeyCode::ControlLeft), 1.0)
+            .bind(A::Fire, S::MouseButton(MouseButton::Left), 1.0)
+            .bind(A::Run, S::Key(KeyCode::ShiftLeft), 1.0)
+            .bind(A::NextWeapon, S::Key(KeyCode::KeyE), 1.0)
+            .bind(A::PreviousWeapon, S::Key(KeyCode::KeyQ), 1.0)
+            .bind(A::MoveForward, S::GamepadAxis(GamepadAxisType::LeftStickY), 1.0)
+            .bind(A::Strafe, S::GamepadAxis(GamepadAxisType::LeftStickX), 1.0)
+            .bind(A::Turn, S::GamepadAxis(GamepadAxisType::RightStickX), 1.0)
+            .bind(A::Look, S::GamepadAxis(GamepadAxisType::RightStickY), 1.0)
+            .bind(A::Use, S::GamepadButton(GamepadButtonType::South), 1.0)
+            .bind(A::Fire, S::GamepadButton(GamepadButtonType::RightTrigger2), 1.0)
+            .bind(A::Run, S::GamepadButton(GamepadButtonType::LeftThumb), 1.0)
+            .bind(A::NextWeapon, S::GamepadButton(GamepadButtonType::RightTrigger), 1.0)
+            .bind(A::PreviousWeapon, S::GamepadButton(GamepadButtonType::LeftTrigger), 1.0);
+        map
+    }
+}
+
+impl WolfInputMap {
+    /// The keyboard only controls of the original game, where the arrow keys walk and turn,
+    /// `Alt` strafes, `Shift` runs and `Control` fires, together with the default gamepad bindings.
+    ///
+    /// Usually used together with `WolfConfig::lock_pitch`.
+    pub fn classic() -> Self {
+        use WolfAction as A;
+        use WolfInputSource as S;
+        let mut map = Self::default();
+        map.bindings.retain(|b| matches!(b.source, S::GamepadButton(_) | S::GamepadAxis(_)));
+        map.bind(A::MoveForward, S::Key(KeyCode::ArrowUp), 1.0)
+            .bind(A::MoveForward, S::Key(KeyCode::ArrowDown), -1.0)
+            .bind(A::Turn, S::Key(KeyCode::ArrowLeft), -1.0)
+            .bind(A::Turn, S::Key(KeyCode::ArrowRight), 1.0)
+            .bind(A::StrafeModifier, S::Key(KeyCode::AltLeft), 1.0)
+            .bind(A::Strafe, S::Key(KeyCode::Comma), -1.0)
+            .bind(A::Strafe, S::Key(KeyCode::Period), 1.0)
+            .bind(A::Use, S::Key(KeyCode::Space), 1.0)
+            .bind(A::Fire, S::Key(KeyCode::ControlLeft), 1.0)
+            .bind(A::Run, S::Key(KeyCode::ShiftLeft), 1.0)
+            .bind(A::NextWeapon, S::Key(KeyCode::KeyE), 1.0)
+            .bind(A::PreviousWeapon, S::Key(KeyCode::KeyQ), 1.0);
+        map
+    }
+
+    /// Adds a binding, keeping the other bindings of the action.
+    pub fn bind(&mut self, action: WolfAction, source: WolfInputSource, scale: f32) -> &mut Self {
+        self.bindings.push(WolfBinding { action, source, scale });
+        self
+    }
+
+    /// Binds the action to the source only, e.g. when the player rebinds it in a menu.
+    pub fn rebind(&mut self, action: WolfAction, source: WolfInputSource, scale: f32) -> &mut Self {
+        self.unbind_action(action);
+        self.unbind_source(source);
+        self.bind(action, source, scale)
+    }
+
+    pub fn unbind_action(&mut self, action: WolfAction) -> &mut Self {
+        self.bindings.retain(|b| b.action != action);
+        self
+    }
+
+    pub fn unbind_source(&mut self, source: WolfInputSource) -> &mut Self {
+        self.bindings.retain(|b| b.source != source);
+        self
+    }
+
+    pub fn bindings_of(&self, action: WolfAction) -> impl Iterator<Item = &WolfBinding> {
+        self.bindings.iter().filter(move |b| b.action == action)
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, WolfInputMapError> {
+        Ok(ron::de::from_str(text)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, WolfInputMapError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Reads the bindings from a RON file, e.g. a config file next to the game.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WolfInputMapError> {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WolfInputMapError> {
+        Ok(std::fs::write(path, self.to_ron()?)?)
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+struct WolfActionState {
+    value: f32,
+    motion: f32,
+    pressed: bool,
+    just_pressed: bool,
+}
+
+/// The state of the actions of one player since the last simulation tick.
+#[derive(Clone, Default, Debug)]
+pub struct WolfActions {
+    states: HashMap<WolfAction, WolfActionState>,
+}
+
+impl WolfActions {
+    /// How far the held inputs of the action are pressed, from -1 to 1.
+    pub fn value(&self, action: WolfAction) -> f32 {
+        self.states.get(&action).map_or(0.0, |s| s.value)
+    }
+
+    /// How far the moved inputs of the action, e.g. the mouse, moved since the last tick.
+    pub fn motion(&self, action: WolfAction) -> f32 {
+        self.states.get(&action).map_or(0.0, |s| s.motion)
+    }
+
+    pub fn pressed(&self, action: WolfAction) -> bool {
+        self.states.get(&action).is_some_and(|s| s.pressed)
+    }
+
+    /// Whether the action was pressed since the last tick, even if it has been released again.
+    pub fn just_pressed(&self, action: WolfAction) -> bool {
+        self.states.get(&action).is_some_and(|s| s.just_pressed)
+    }
+
+    /// Sets the held value of the action for the current frame.
+    pub(crate) fn hold(&mut self, action: WolfAction, value: f32, tapped: bool) {
+        let state = self.states.entry(action).or_default();
+        let pressed = value != 0.0;
+        state.just_pressed |= tapped || (pressed && !state.pressed);
+        state.value = value;
+        state.pressed = pressed;
+    }
+
+    pub(crate) fn add_motion(&mut self, action: WolfAction, motion: f32) {
+        self.states.entry(action).or_default().motion += motion;
+    }
+
+    /// Forgets the actions which lost all their bindings, as nothing would release them anymore.
+    pub(crate) fn forget_unbound(&mut self, map: &WolfInputMap) {
+        self.states.retain(|action, _| map.bindings_of(*action).next().is_some());
+    }
+
+    /// Forgets what happened since the last tick, keeping what is held.
+    pub(crate) fn consume(&mut self) {
+        for state in self.states.values_mut() {
+            state.motion = 0.0;
+            state.just_pressed = false;
+        }
+    }
+}
+
+/// Input gathered since the last simulation tick, for every local player.
+#[derive(Resource, Default)]
+pub struct WolfInput {
+    pub(crate) players: Vec<WolfActions>,
+}
+
+impl WolfInput {
+    pub fn player(&self, index: usize) -> Option<&WolfActions> {
+        self.players.get(index)
+    }
+}
+
+pub fn build_input(app: &mut App) {
+    app.init_resource::<WolfInput>();
+    app.init_resource::<WolfInputMap>();
+}
@@ -14,14 +14,61 @@ pub use assets::*;
 mod events;
 pub use events::*;
 
+mod classes;
+pub use classes::*;
+
+mod wolf3d;
+pub use wolf3d::*;
+
+mod text_map;
+pub use text_map::*;
+
+mod input;
+pub use input::*;
+
+/// Systems of the `WolfPlugin`, where the simulation runs in `FixedUpdate` at the tick rate
+/// of the `WolfConfig`, and the presentation runs in `Update`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WolfSet {
+    Simulation,
+    Presentation,
+}
+
+/// Adds both the `WolfSimulationPlugin` and the `WolfPresentationPlugin`.
 pub struct WolfPlugin;
 
 impl Plugin for WolfPlugin {
     fn build(&self, app: &mut App) {
-        systems::build_systems(app);
+        app.add_plugins((WolfSimulationPlugin, WolfPresentationPlugin));
+    }
+}
+
+/// Map loading, spawning, input, bodies, doors, push walls and interaction.
+///
+/// Does not depend on rendering, so the gameplay can run headless using `MinimalPlugins`
+/// together with the `AssetPlugin` and the `InputPlugin`.
+pub struct WolfSimulationPlugin;
+
+impl Plugin for WolfSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        systems::build_simulation_systems(app);
         assets::build_assets(app);
-        resources::build_resources(app);
+        wolf3d::build_wolf3d(app);
+        text_map::build_text_map(app);
+        input::build_input(app);
+        resources::build_simulation_resources(app);
         events::build_events(app);
+        classes::build_simulation_classes(app);
     }
 }
 
+/// Meshes, sprites, instancing, UI and gizmos. Must be added after the `WolfSimulationPlugin`.
+pub struct WolfPresentationPlugin;
+
+impl Plugin for WolfPresentationPlugin {
+    fn build(&self, app: &mut App) {
+        systems::build_presentation_systems(app);
+        resources::build_presentation_resources(app);
+        classes::build_presentation_classes(app);
+    }
+}
@@ -4,19 +4,21 @@ use bevy::{
     prelude::*, render::{mesh::Indices, render_asset::RenderAssetUsages}, utils::HashMap
 };
 
-use crate::WolfMap;
+use crate::{WolfBody, WolfCollisionGroups, WolfEntity, WolfMap, WolfMapKey, WolfObjectShape, WolfShape};
 
 
 pub struct WolfGrid {
     spatial:flat_spatial::Grid<(Entity, Vec2), [f32;2]>, 
-    map:HashMap<Entity, flat_spatial::grid::GridHandle>
+    map:HashMap<Entity, flat_spatial::grid::GridHandle>,
+    bodies:HashMap<Entity, WolfBody>
 }
 
 impl Default for WolfGrid {
     fn default() -> Self {
         Self { 
             spatial:flat_spatial::Grid::new(8),
-            map:HashMap::new()
+            map:HashMap::new(),
+            bodies:HashMap::new()
         }
     }
 }
@@ -25,6 +27,20 @@ impl WolfGrid {
     pub fn clear(&mut self) {
         let _ = self.spatial.clear();
         self.map.clear();
+        self.bodies.clear();
+    }
+
+    /// Sets the body of an entity in the grid, used by the world queries.
+    pub fn insert_body(&mut self, entity:Entity, body:WolfBody) {
+        self.bodies.insert(entity, body);
+    }
+
+    pub fn remove_body(&mut self, entity:Entity) {
+        self.bodies.remove(&entity);
+    }
+
+    pub fn body(&self, entity:Entity) -> Option<&WolfBody> {
+        self.bodies.get(&entity)
     }
 
     pub fn insert_or_replace(&mut self, entity:Entity, pos:Vec2) {
@@ -36,6 +52,21 @@ impl WolfGrid {
         self.map.insert(entity, h);
     }
 
+    pub fn remove(&mut self, entity:Entity) {
+        if let Some(handle) = self.map.remove(&entity) {
+            self.spatial.remove_maintain(handle);
+        }
+        self.bodies.remove(&entity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
     pub fn query(&self, pos:Vec2, half_extent:f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
         let iter = self.spatial.query([pos.x - half_extent, pos.y - half_extent], [pos.x + half_extent, pos.y + half_extent]);
         let iter = iter.map(|x|*self.spatial.get(x.0).unwrap().1);
@@ -50,19 +81,441 @@ impl WolfGrid {
 }
 
 
+/// The static blocks of the map, one entry per tile.
+///
+/// Blocks never move, so bodies are resolved against this grid instead of
+/// having every wall in the `WolfGrid`.
+#[derive(Default)]
+pub struct WolfTileGrid {
+    width:u32,
+    height:u32,
+    tiles:Vec<Option<WolfCollisionGroups>>
+}
+
+impl WolfTileGrid {
+    pub fn from_map(map:&WolfMap) -> Self {
+        let mut grid = Self {
+            width:map.width,
+            height:map.height,
+            tiles:vec![None; (map.width * map.height) as usize]
+        };
+        for layer in map.layers.iter() {
+            for (y, row) in layer.rows_iter().enumerate() {
+                for (x, tile) in row.enumerate() {
+                    let Some(we) = tile else {
+                        continue;
+                    };
+                    if Self::is_static_block(we) {
+                        grid.tiles[y * map.width as usize + x] = Some(WolfCollisionGroups::from_entity(we));
+                    }
+                }
+            }
+        }
+        // push walls are blocks that move
+        let pushes = map.layers.iter()
+            .flat_map(|layer| layer.elements_row_major_iter().flatten())
+            .chain(map.objects.iter())
+            .filter(|we| we.has_class("push"));
+        for we in pushes {
+            let tile = we.start_pos.truncate().floor().as_ivec2();
+            if let Some(index) = grid.index(tile) {
+                grid.tiles[index] = None;
+            }
+        }
+        grid
+    }
+
+    /// Whether the entity is a block filling its whole tile, given it is not a push wall.
+    pub fn is_static_block(we:&WolfEntity) -> bool {
+        if !we.has_class("block") || !we.has_class("body") || we.object_shape != WolfObjectShape::Tile {
+            return false;
+        }
+        match WolfShape::from_entity(we) {
+            Ok(WolfShape::Cuboid(cuboid)) => cuboid.half_extents.x == 0.5 && cuboid.half_extents.y == 0.5,
+            _ => false
+        }
+    }
+
+    fn index(&self, tile:IVec2) -> Option<usize> {
+        if tile.x < 0 || tile.y < 0 || tile.x >= self.width as i32 || tile.y >= self.height as i32 {
+            return None;
+        }
+        Some(tile.y as usize * self.width as usize + tile.x as usize)
+    }
+
+    /// Whether a walk in the direction of `step` has left the grid for good.
+    fn leaving(&self, tile:IVec2, step:IVec2) -> bool {
+        (tile.x < 0 && step.x <= 0) || (tile.y < 0 && step.y <= 0)
+            || (tile.x >= self.width as i32 && step.x >= 0) || (tile.y >= self.height as i32 && step.y >= 0)
+    }
+
+    /// Returns the collision groups of the static block in the tile, if any.
+    pub fn get(&self, tile:IVec2) -> Option<WolfCollisionGroups> {
+        self.index(tile).and_then(|index| self.tiles[index])
+    }
+
+    /// Returns the static blocks of the tiles overlapping the area.
+    pub fn overlapping(&self, mins:Vec2, maxs:Vec2) -> impl Iterator<Item = (IVec2, WolfCollisionGroups)> + '_ {
+        let mins = mins.floor().as_ivec2();
+        let maxs = maxs.floor().as_ivec2();
+        (mins.y..=maxs.y)
+            .flat_map(move |y| (mins.x..=maxs.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|tile| self.get(tile).map(|groups| (tile, groups)))
+    }
+}
+
 #[derive(Default, Resource)]
 pub struct WolfWorld {
     pub updates:u64,
     pub map: WolfMap,
     pub grid: WolfGrid,
+    pub tiles: WolfTileGrid,
     pub last_ui_update:f32,
     pub(crate) map_handle: Option<Handle<WolfMap>>,
+    pub(crate) current_map_handle: Option<Handle<WolfMap>>,
+    pub(crate) entities: HashMap<WolfMapKey, Entity>,
+}
+
+/// The faces of a tile, named by the direction they face, where north is towards +y.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WolfTileFace {
+    North,
+    South,
+    East,
+    West
+}
+
+impl WolfTileFace {
+    pub fn normal(&self) -> Vec2 {
+        match self {
+            WolfTileFace::North => Vec2::Y,
+            WolfTileFace::South => Vec2::NEG_Y,
+            WolfTileFace::East => Vec2::X,
+            WolfTileFace::West => Vec2::NEG_X
+        }
+    }
+}
+
+/// What a query of the `WolfWorld` collides with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WolfQueryFilter {
+    /// Tested against the groups of the bodies like the groups of another body.
+    pub groups:WolfCollisionGroups,
+    /// An entity to ignore, typically the one doing the query.
+    pub exclude:Option<Entity>
+}
+
+impl WolfQueryFilter {
+    pub fn new(groups:WolfCollisionGroups) -> Self {
+        Self { groups, exclude: None }
+    }
+
+    pub fn excluding(mut self, entity:Entity) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
+
+    fn accepts(&self, entity:Entity, groups:&WolfCollisionGroups) -> bool {
+        self.exclude != Some(entity) && self.groups.test(groups)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WolfRayHit {
+    pub entity:Entity,
+    pub point:Vec2,
+    /// Zero if the ray started inside what it hit.
+    pub normal:Vec2,
+    pub distance:f32,
+    /// The face of the static block that was hit, if it was one.
+    pub tile_face:Option<WolfTileFace>
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct WolfSweepHit {
+    pub entity:Entity,
+    /// The fraction of the motion at which the shape hit, from 0 to 1.
+    pub toi:f32,
+    /// Where the shape was when it hit.
+    pub position:Vec2,
+    pub point:Vec2,
+    /// Points away from what was hit.
+    pub normal:Vec2,
+    /// How far the rest of the motion would have moved the shape into what was hit.
+    pub depth:f32
+}
+
+/// Where `WolfWorld::move_and_slide` moved a shape to and what it slid along on the way.
+#[derive(Clone, Debug)]
+pub struct WolfMove {
+    pub position:Vec2,
+    /// The part of the motion that was blocked.
+    pub remaining:Vec2,
+    pub hits:Vec<WolfSweepHit>
+}
+
+/// How far bodies are assumed to reach beyond their position when looking them up in the `WolfGrid`.
+pub(crate) const BODY_REACH:f32 = 2.0;
+/// How far along a ray the `WolfGrid` is looked up at once.
+const RAY_PIECE:f32 = 8.0;
+
 impl WolfWorld {
+    /// A float property of an entity, or else of the current map, for settings which can be
+    /// given per entity and per map.
+    pub fn property_f32(&self, we:&WolfEntity, property:&str) -> Option<f32> {
+        we.get_property_f32(property).or(self.map.properties.get_property_f32(property)).copied()
+    }
+
+    /// Returns the first body or static block hit by a ray.
+    ///
+    /// Static blocks are found by walking the `WolfTileGrid` tile by tile, while other bodies are
+    /// found through the `WolfGrid` as of the last tick. Disabled bodies are not hit, so doors
+    /// block rays until they are fully open. Rays end beyond the edge of the map.
+    pub fn raycast(&self, origin:Vec2, dir:Vec2, max_dist:f32, filter:WolfQueryFilter) -> Option<WolfRayHit> {
+        let dir = dir.normalize_or_zero();
+        if dir == Vec2::ZERO {
+            return None;
+        }
+        let size = Vec2::new(self.map.width as f32, self.map.height as f32);
+        let farthest = origin.abs().max((origin - size).abs()).length() + BODY_REACH;
+        let max_dist = max_dist.min(farthest);
+        let mut hit = self.raycast_tiles(origin, dir, max_dist, &filter);
+
+        // the ray is looked up piece by piece, as the box around a long diagonal ray covers far more than the ray
+        let ray = parry2d::query::Ray::new([origin.x, origin.y].into(), [dir.x, dir.y].into());
+        let mut seen = Vec::new();
+        let mut start = 0.0;
+        while start < hit.map_or(max_dist, |hit| hit.distance) {
+            let end = (start + RAY_PIECE).min(max_dist);
+            let center = origin + dir * (start + end) / 2.0;
+            for (e, p) in self.grid.query(center, (end - start) / 2.0 + BODY_REACH) {
+                if seen.contains(&e) {
+                    continue;
+                }
+                seen.push(e);
+                let Some(body) = self.grid.body(e) else {
+                    continue;
+                };
+                if body.disabled || !filter.accepts(e, &body.groups) {
+                    continue;
+                }
+                let max_toi = hit.map_or(max_dist, |hit| hit.distance);
+                let position = parry2d::math::Isometry::translation(p.x, p.y);
+                if let Some(intersection) = body.shape.as_shape().cast_ray_and_get_normal(&position, &ray, max_toi, true) {
+                    if hit.is_none_or(|hit| intersection.toi < hit.distance) {
+                        hit = Some(WolfRayHit {
+                            entity: e,
+                            point: origin + dir * intersection.toi,
+                            normal: Vec2::new(intersection.normal.x, intersection.normal.y),
+                            distance: intersection.toi,
+                            tile_face: None,
+                        });
+                    }
+                }
+            }
+            start = end;
+        }
+        hit
+    }
+
+    /// Returns the bodies and static blocks overlapping a shape at a position.
+    ///
+    /// Like with triggers, bodies merely touching the shape are not overlapping it.
+    pub fn overlap(&self, shape:&WolfShape, pos:Vec2, filter:WolfQueryFilter) -> Vec<Entity> {
+        let position = parry2d::math::Isometry::translation(pos.x, pos.y);
+        let penetrates = |other_pos:Vec2, other:&dyn parry2d::shape::Shape| {
+            let other_position = parry2d::math::Isometry::translation(other_pos.x, other_pos.y);
+            matches!(parry2d::query::contact(&position, shape.as_shape(), &other_position, other, 0.0), Ok(Some(c)) if c.dist < 0.0)
+        };
+
+        let mut entities = Vec::new();
+        let tile_shape = parry2d::shape::Cuboid::new([0.5, 0.5].into());
+        let aabb = shape.as_shape().compute_aabb(&position);
+        let (mins, maxs) = (Vec2::new(aabb.mins.x, aabb.mins.y), Vec2::new(aabb.maxs.x, aabb.maxs.y));
+        for (tile, _) in self.tiles.overlapping(mins, maxs) {
+            if penetrates(tile.as_vec2() + 0.5, &tile_shape) {
+                entities.extend(self.static_block(tile, &filter));
+            }
+        }
+
+        let half_extent = (maxs - mins).max_element() / 2.0 + BODY_REACH;
+        for (e, p) in self.grid.query((mins + maxs) / 2.0, half_extent) {
+            let Some(body) = self.grid.body(e) else {
+                continue;
+            };
+            if !body.disabled && filter.accepts(e, &body.groups) && penetrates(p, body.shape.as_shape()) {
+                entities.push(e);
+            }
+        }
+        entities
+    }
+
+    /// Moves a shape from one position towards another, returning the first body or static block it hits.
+    ///
+    /// Shapes touching the moving shape at the start are only hit if it moves into them.
+    pub fn sweep(&self, shape:&WolfShape, from:Vec2, to:Vec2, filter:WolfQueryFilter) -> Option<WolfSweepHit> {
+        let motion = to - from;
+        let position = parry2d::math::Isometry::translation(from.x, from.y);
+        let velocity = [motion.x, motion.y].into();
+        let mut hit:Option<WolfSweepHit> = None;
+        let mut cast = |entity:Entity, other_pos:Vec2, other:&dyn parry2d::shape::Shape| {
+            let other_position = parry2d::math::Isometry::translation(other_pos.x, other_pos.y);
+            let max_toi = hit.map_or(1.0, |hit| hit.toi);
+            let Ok(Some(toi)) = parry2d::query::time_of_impact(
+                &position, &velocity, shape.as_shape(), &other_position, &[0.0, 0.0].into(), other, max_toi, true,
+            ) else {
+                return;
+            };
+            let normal = Vec2::new(toi.normal1.x, toi.normal1.y);
+            if normal.dot(motion) <= 0.0 || hit.is_some_and(|hit| toi.toi >= hit.toi) {
+                return;
+            }
+            // grazing contacts, e.g. at the corners of walls the shape slides along, and shapes already
+            // overlapping at the start are only hit if moving on a little would move noticeably deeper into them
+            let touching = 1e-4;
+            let depth = |at:&parry2d::math::Isometry<f32>| match parry2d::query::contact(at, shape.as_shape(), &other_position, other, 0.0) {
+                Ok(Some(c)) => -c.dist.min(0.0),
+                _ => 0.0,
+            };
+            let length = motion.length();
+            let past = from + motion * toi.toi + motion / length * (length * (1.0 - toi.toi)).min(0.01);
+            if depth(&parry2d::math::Isometry::translation(past.x, past.y)) < depth(&position) + touching {
+                return;
+            }
+            let center = from + motion * toi.toi;
+            hit = Some(WolfSweepHit {
+                entity,
+                toi: toi.toi,
+                position: center,
+                point: center + Vec2::new(toi.witness1.x, toi.witness1.y),
+                normal: -normal,
+                depth: (motion * (1.0 - toi.toi)).dot(normal),
+            });
+        };
+
+        let tile_shape = parry2d::shape::Cuboid::new([0.5, 0.5].into());
+        let aabb = shape.as_shape().compute_swept_aabb(&position, &parry2d::math::Isometry::translation(to.x, to.y));
+        let (mins, maxs) = (Vec2::new(aabb.mins.x, aabb.mins.y), Vec2::new(aabb.maxs.x, aabb.maxs.y));
+        for (tile, _) in self.tiles.overlapping(mins, maxs) {
+            if let Some(entity) = self.static_block(tile, &filter) {
+                let center = tile.as_vec2() + 0.5;
+                cast(entity, center, &tile_shape);
+            }
+        }
+
+        let half_extent = (maxs - mins).max_element() / 2.0 + BODY_REACH;
+        for (e, p) in self.grid.query((mins + maxs) / 2.0, half_extent) {
+            let Some(body) = self.grid.body(e) else {
+                continue;
+            };
+            if !body.disabled && filter.accepts(e, &body.groups) {
+                cast(e, p, body.shape.as_shape());
+            }
+        }
+        hit
+    }
+
+    /// Moves a shape by a motion, sliding along what it hits instead of stopping.
+    pub fn move_and_slide(&self, shape:&WolfShape, from:Vec2, motion:Vec2, filter:WolfQueryFilter) -> WolfMove {
+        // stops the shape short of what it hits, so it does not start the next sweep inside it
+        let skin = 0.001;
+        let max_slides = 4;
+        let mut position = from;
+        let mut left = motion;
+        let mut hits = Vec::new();
+        for _ in 0..max_slides {
+            if left.length_squared() < 1e-8 {
+                break;
+            }
+            let Some(hit) = self.sweep(shape, position, position + left, filter) else {
+                position += left;
+                break;
+            };
+            let travel = left * hit.toi;
+            position += travel - travel.clamp_length_max(skin);
+            let rest = left * (1.0 - hit.toi);
+            left = rest - hit.normal * rest.dot(hit.normal);
+            hits.push(hit);
+        }
+        WolfMove {
+            position,
+            remaining: motion - (position - from),
+            hits,
+        }
+    }
+
+    /// Walks the tiles crossed by the ray, where `dir` is normalized.
+    fn raycast_tiles(&self, origin:Vec2, dir:Vec2, max_dist:f32, filter:&WolfQueryFilter) -> Option<WolfRayHit> {
+        let mut tile = origin.floor().as_ivec2();
+        if let Some(entity) = self.static_block(tile, filter) {
+            return Some(WolfRayHit { entity, point: origin, normal: Vec2::ZERO, distance: 0.0, tile_face: None });
+        }
+
+        let step = IVec2::new(dir.x.signum() as i32, dir.y.signum() as i32);
+        // the distance along the ray to cross a whole tile, and to cross into the next tile
+        let delta = Vec2::new(1.0 / dir.x.abs(), 1.0 / dir.y.abs());
+        let next_boundary = |p:f32, tile:i32, d:f32| {
+            if d > 0.0 {
+                (tile as f32 + 1.0 - p) / d
+            } else if d < 0.0 {
+                (p - tile as f32) / -d
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut next = Vec2::new(next_boundary(origin.x, tile.x, dir.x), next_boundary(origin.y, tile.y, dir.y));
+        loop {
+            let (distance, face) = if next.x < next.y {
+                tile.x += step.x;
+                next.x += delta.x;
+                (next.x - delta.x, if step.x > 0 { WolfTileFace::West } else { WolfTileFace::East })
+            } else {
+                tile.y += step.y;
+                next.y += delta.y;
+                (next.y - delta.y, if step.y > 0 { WolfTileFace::South } else { WolfTileFace::North })
+            };
+            if distance > max_dist || self.tiles.leaving(tile, step) {
+                return None;
+            }
+            if let Some(entity) = self.static_block(tile, filter) {
+                return Some(WolfRayHit {
+                    entity,
+                    point: origin + dir * distance,
+                    normal: face.normal(),
+                    distance,
+                    tile_face: Some(face),
+                });
+            }
+        }
+    }
+
+    /// Returns the entity of the static block in the tile if the filter accepts it.
+    pub(crate) fn static_block(&self, tile:IVec2, filter:&WolfQueryFilter) -> Option<Entity> {
+        let groups = self.tiles.get(tile)?;
+        let (x, y) = (tile.x as u32, tile.y as u32);
+        self.map.layers.iter().enumerate()
+            .filter(|(_, layer)| layer.get(y as usize, x as usize).is_some_and(|we| we.as_ref().is_some_and(WolfTileGrid::is_static_block)))
+            .find_map(|(layer, _)| self.entities.get(&WolfMapKey::Tile { layer, x, y }).copied())
+            .filter(|entity| filter.accepts(*entity, &groups))
+    }
+
+    /// Loads the map, replacing the current map once loaded.
+    ///
+    /// Changes to the map on disk are applied in place while it is the current map,
+    /// given the `file_watcher` feature of this crate, or of bevy, is enabled.
     pub fn load_map(&mut self, handle: Handle<WolfMap>) {
         self.map_handle = Some(handle);
     }
+
+    /// Returns the entity spawned for the map object with the given id.
+    pub fn object_entity(&self, object_id:u32) -> Option<Entity> {
+        self.entities.get(&WolfMapKey::Object(object_id)).copied()
+    }
+
+    /// Returns the entity spawned for the tile or object with the given key.
+    pub fn map_entity(&self, key:WolfMapKey) -> Option<Entity> {
+        self.entities.get(&key).copied()
+    }
 }
 
 pub struct AssetMap<T : Asset + TypePath + Send + Sync> {
@@ -150,34 +603,333 @@ impl WolfSpriteMesh {
 
 #[derive(Resource)]
 pub struct WolfConfig {
-    pub interaction_key: KeyCode,
-    pub forward_key: KeyCode,
-    pub backward_key: KeyCode,
-    pub strife_left_key: KeyCode,
-    pub strife_right_key: KeyCode,
+    /// Radians per pixel the mouse moves when turning or looking.
     pub turn_speed:f32,
-    pub show_dev:bool
+    /// Radians per second when turning or looking with a held input, e.g. the arrow keys
+    /// of `WolfInputMap::classic` or a stick pushed all the way.
+    pub turn_rate:f32,
+    /// Keeps the view level with the horizon, ignoring the `Look` action.
+    pub lock_pitch:bool,
+    /// How much faster players move while holding the `Run` action.
+    pub run_multiplier:f32,
+    /// Walking speed of players in tiles per second, unless given by a `move_speed` property.
+    pub move_speed:f32,
+    /// Seconds doors take to open or close, unless given by a `door_open_time` property.
+    pub door_open_time:f32,
+    /// Seconds doors stay open before closing by themselves, or `None` to keep them open,
+    /// unless given by a `door_auto_close` property, where a negative value keeps them open.
+    pub door_auto_close:Option<f32>,
+    /// Tiles per second push walls move, unless given by a `push_speed` property.
+    pub push_speed:f32,
+    /// How many tiles push walls move, or `None` to move until blocked, unless given by a
+    /// `push_distance` property, where a negative value moves until blocked.
+    pub push_distance:Option<f32>,
+    /// Vertical field of view of cameras in degrees, unless given by a `fov` property.
+    pub fov:f32,
+    /// Height of the head bob in tiles when walking at the speed of the `WolfController`, e.g. 0.02, where 0 turns it off.
+    pub head_bob:f32,
+    /// Tiles walked per bob of the head.
+    pub head_bob_length:f32,
+    /// Degrees the view rolls when strafing at the speed of the `WolfController`, e.g. 1.5, where 0 turns it off.
+    pub view_roll:f32,
+    /// Degrees the view kicks up for a `WolfFlinchEvent` with an amount of 1.
+    pub flinch:f32,
+    /// Tiles the view shakes for a `WolfShakeEvent` with a strength of 1.
+    pub shake:f32,
+    /// How fast the view effects settle, per second.
+    pub view_recovery:f32,
+    pub show_dev:bool,
+    /// Simulation ticks per second.
+    pub tick_rate:f64,
+    /// Sends at most one `WolfCollisionEvent` per pair of bodies and tick, the deepest one.
+    pub dedup_collision_events:bool,
+    /// Rebuilds the `WolfGrid` from every entity each tick instead of only updating what changed,
+    /// which is slower and meant for comparing the two, e.g. in the benchmarks.
+    pub rebuild_grid:bool
 }
 
 impl Default for WolfConfig {
     fn default() -> Self {
         Self {
-            interaction_key: KeyCode::Space,
-            forward_key: KeyCode::KeyW,
-            backward_key: KeyCode::KeyS,
-            strife_left_key: KeyCode::KeyA,
-            strife_right_key: KeyCode::KeyD,
             turn_speed:0.01,
-            show_dev:false
+            turn_rate:3.0,
+            lock_pitch:false,
+            run_multiplier:2.0,
+            move_speed:10.0,
+            door_open_time:0.5,
+            door_auto_close:Some(3.0),
+            push_speed:1.0,
+            push_distance:None,
+            fov:45.0,
+            head_bob:0.0,
+            head_bob_length:1.2,
+            view_roll:0.0,
+            flinch:4.0,
+            shake:0.05,
+            view_recovery:6.0,
+            show_dev:false,
+            tick_rate:60.0,
+            dedup_collision_events:false,
+            rebuild_grid:false
         }
     }
 }
 
-
-
-pub fn build_resources(app: &mut App) {
+pub fn build_simulation_resources(app: &mut App) {
     app.init_resource::<WolfWorld>();
-    app.init_resource::<WolfAssets>();
     app.init_resource::<WolfConfig>();
 }
 
+pub fn build_presentation_resources(app: &mut App) {
+    app.init_resource::<WolfAssets>();
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_text_map;
+
+    fn map(text:&str) -> WolfMap {
+        parse_text_map(std::path::Path::new("maps/test.wolfmap"), text).unwrap()
+    }
+
+    /// A world as spawned from the map, with an entity per tile and the bodies at their start.
+    fn world(text:&str) -> WolfWorld {
+        let map = map(text);
+        let mut world = WolfWorld { tiles: WolfTileGrid::from_map(&map), ..Default::default() };
+        for (index, (key, we)) in map.entities().enumerate() {
+            let e = Entity::from_raw(index as u32);
+            world.entities.insert(key, e);
+            if we.has_class("body") && !WolfTileGrid::is_static_block(we) {
+                world.grid.insert_or_replace(e, we.start_pos().truncate());
+                world.grid.insert_body(e, WolfBody {
+                    shape: WolfShape::from_entity(we).unwrap(),
+                    groups: WolfCollisionGroups::from_entity(we),
+                    ..Default::default()
+                });
+            }
+        }
+        world.map = map;
+        world
+    }
+
+    fn entity_at(world:&WolfWorld, x:u32, y:u32) -> Entity {
+        (0..world.map.layers.len())
+            .find_map(|layer| world.map_entity(WolfMapKey::Tile { layer, x, y }))
+            .unwrap_or_else(|| panic!("no entity at ({}, {})", x, y))
+    }
+
+    #[test]
+    fn tile_grid() {
+        // the last row of the grid is y = 0
+        let map = map(
+            "[legend]\n# = block body\nb = block\no = block body; body_shape = ball\np = push\n\
+             [grid]\n####\n#.b#\n#o.#\n####\n\
+             [grid]\n....\n....\n....\n.p..\n",
+        );
+        let tiles = WolfTileGrid::from_map(&map);
+        for (x, y) in [(0, 0), (2, 0), (3, 0), (0, 1), (3, 2), (0, 3), (3, 3)] {
+            assert!(tiles.get(IVec2::new(x, y)).is_some(), "no block at ({}, {})", x, y);
+        }
+        // a push wall, a block without a body and a block that is not a cuboid
+        assert!(tiles.get(IVec2::new(1, 0)).is_none());
+        assert!(tiles.get(IVec2::new(2, 2)).is_none());
+        assert!(tiles.get(IVec2::new(1, 1)).is_none());
+        assert!(tiles.get(IVec2::new(2, 1)).is_none());
+
+        for tile in [IVec2::new(-1, 0), IVec2::new(0, -1), IVec2::new(4, 0), IVec2::new(0, 4), IVec2::new(i32::MIN, i32::MAX)] {
+            assert!(tiles.get(tile).is_none(), "block outside the map at {}", tile);
+        }
+    }
+
+    #[test]
+    fn tile_grid_groups() {
+        let map = map("[legend]\n# = block body\nx = block body; body_memberships = 2; body_filter = 4\n[grid]\n#x\n");
+        let tiles = WolfTileGrid::from_map(&map);
+        assert_eq!(tiles.get(IVec2::new(0, 0)), Some(WolfCollisionGroups::default()));
+        assert_eq!(tiles.get(IVec2::new(1, 0)), Some(WolfCollisionGroups::new(2, 4)));
+    }
+
+    #[test]
+    fn static_block() {
+        let map = map(
+            "[legend]\n# = block body\nb = block\nc = block body; body_half_extent_x = 0.25\nr = block body; body_half_extent_x = 0.5\n\
+             [grid]\n#bcr\n",
+        );
+        let we = |x:usize| map.layers[0][(0, x)].as_ref().unwrap();
+        assert!(WolfTileGrid::is_static_block(we(0)));
+        assert!(!WolfTileGrid::is_static_block(we(1)));
+        assert!(!WolfTileGrid::is_static_block(we(2)));
+        assert!(WolfTileGrid::is_static_block(we(3)));
+
+        let object = WolfEntity { object_shape: WolfObjectShape::Rect { half_extents: Vec2::splat(0.5) }, ..we(0).clone() };
+        assert!(!WolfTileGrid::is_static_block(&object));
+    }
+
+    #[test]
+    fn tile_grid_overlapping() {
+        let map = map("[legend]\n# = block body\n[grid]\n###\n#..\n#.#\n");
+        let tiles = WolfTileGrid::from_map(&map);
+        let overlapping = |mins:Vec2, maxs:Vec2| {
+            let mut found:Vec<IVec2> = tiles.overlapping(mins, maxs).map(|(tile, _)| tile).collect();
+            found.sort_by_key(|tile| (tile.x, tile.y));
+            found
+        };
+        assert_eq!(overlapping(Vec2::new(1.2, 1.2), Vec2::new(1.8, 1.8)), vec![]);
+        assert_eq!(overlapping(Vec2::new(1.5, 0.5), Vec2::new(2.5, 1.5)), vec![IVec2::new(2, 0)]);
+        // tiles past the edge of the map are skipped, including those with negative coordinates
+        assert_eq!(overlapping(Vec2::new(-3.0, -3.0), Vec2::new(0.5, 0.5)), vec![IVec2::new(0, 0)]);
+        assert_eq!(
+            overlapping(Vec2::new(1.5, 2.5), Vec2::new(5.0, 5.0)),
+            vec![IVec2::new(1, 2), IVec2::new(2, 2)]
+        );
+        assert_eq!(overlapping(Vec2::new(-5.0, 4.0), Vec2::new(10.0, 8.0)), vec![]);
+        // an area ending exactly on a tile edge reaches into the next tile
+        assert_eq!(overlapping(Vec2::new(1.2, 1.2), Vec2::new(1.5, 2.0)), vec![IVec2::new(1, 2)]);
+    }
+
+    #[test]
+    fn raycast_faces() {
+        let world = world("[legend]\n# = block body\n[grid]\n#####\n#...#\n#...#\n#...#\n#####\n");
+        let origin = Vec2::new(2.5, 2.5);
+        for (dir, tile, face, point) in [
+            (Vec2::X, (4, 2), WolfTileFace::West, Vec2::new(4.0, 2.5)),
+            (Vec2::NEG_X, (0, 2), WolfTileFace::East, Vec2::new(1.0, 2.5)),
+            (Vec2::Y, (2, 4), WolfTileFace::South, Vec2::new(2.5, 4.0)),
+            (Vec2::NEG_Y, (2, 0), WolfTileFace::North, Vec2::new(2.5, 1.0)),
+        ] {
+            let hit = world.raycast(origin, dir, 10.0, WolfQueryFilter::default()).unwrap();
+            assert_eq!(hit.entity, entity_at(&world, tile.0, tile.1));
+            assert_eq!(hit.tile_face, Some(face));
+            assert_eq!(hit.normal, face.normal());
+            assert!(hit.point.distance(point) < 1e-5 && (hit.distance - 1.5).abs() < 1e-5, "{:?}", hit);
+        }
+        assert!(world.raycast(origin, Vec2::X, 1.4, WolfQueryFilter::default()).is_none());
+
+        // a diagonal ray hits the face it crosses first
+        let hit = world.raycast(Vec2::new(1.5, 2.5), Vec2::new(1.0, 2.0), 10.0, WolfQueryFilter::default()).unwrap();
+        assert_eq!((hit.entity, hit.tile_face), (entity_at(&world, 2, 4), Some(WolfTileFace::South)));
+        let hit = world.raycast(Vec2::new(1.2, 1.2), Vec2::new(-1.0, -1.0), 10.0, WolfQueryFilter::default()).unwrap();
+        assert!(hit.entity == entity_at(&world, 0, 1) || hit.entity == entity_at(&world, 1, 0));
+    }
+
+    #[test]
+    fn raycast_door() {
+        let mut world = world(
+            "[legend]\n# = block body\nD = door body; body_memberships = 2\n[grid]\n#.D.#\n",
+        );
+        let door = entity_at(&world, 2, 0);
+        let wall = entity_at(&world, 4, 0);
+        let origin = Vec2::new(1.5, 0.5);
+        let hit = world.raycast(origin, Vec2::X, 10.0, WolfQueryFilter::default()).unwrap();
+        assert_eq!((hit.entity, hit.tile_face), (door, None));
+        assert!((hit.distance - 0.5).abs() < 1e-5 && hit.normal.distance(Vec2::NEG_X) < 1e-5, "{:?}", hit);
+
+        // the ray passes through the door when it is excluded or filtered out
+        let hit = world.raycast(origin, Vec2::X, 10.0, WolfQueryFilter::default().excluding(door)).unwrap();
+        assert_eq!((hit.entity, hit.distance), (wall, 2.5));
+        let filter = WolfQueryFilter::new(WolfCollisionGroups::new(u32::MAX, !2));
+        assert_eq!(world.raycast(origin, Vec2::X, 10.0, filter).unwrap().entity, wall);
+        let filter = WolfQueryFilter::new(WolfCollisionGroups::new(u32::MAX, 0));
+        assert!(world.raycast(origin, Vec2::X, 10.0, filter).is_none());
+
+        // an open door does not block the ray
+        let mut body = world.grid.body(door).unwrap().clone();
+        body.disabled = true;
+        world.grid.insert_body(door, body);
+        assert_eq!(world.raycast(origin, Vec2::X, 10.0, WolfQueryFilter::default()).unwrap().entity, wall);
+    }
+
+    #[test]
+    fn raycast_unbounded() {
+        let empty = world("[legend]\no = body; body_shape = ball\n[grid]\n...\n...\n");
+        for origin in [Vec2::new(1.5, 0.5), Vec2::new(-100.0, 50.0)] {
+            for dir in [Vec2::X, Vec2::new(1.0, 1.0), Vec2::new(-3.0, 1.0), Vec2::NEG_Y] {
+                assert!(empty.raycast(origin, dir, f32::INFINITY, WolfQueryFilter::default()).is_none());
+                assert!(empty.raycast(origin, dir, f32::MAX, WolfQueryFilter::default()).is_none());
+            }
+        }
+
+        // bodies far along a diagonal ray are found
+        let size = 40;
+        let mut grid = vec![".".repeat(size); size];
+        grid[2].replace_range(size - 3..size - 2, "o");
+        let world = world(&format!("[legend]\no = body; body_shape = ball\n[grid]\n{}\n", grid.join("\n")));
+        let ball = entity_at(&world, size as u32 - 3, size as u32 - 3);
+        let hit = world.raycast(Vec2::splat(0.5), Vec2::ONE, f32::INFINITY, WolfQueryFilter::default()).unwrap();
+        assert_eq!(hit.entity, ball);
+        assert!(hit.point.distance(Vec2::splat(size as f32 - 2.5 - 0.5 / 2f32.sqrt())) < 1e-3, "{:?}", hit);
+    }
+
+    #[test]
+    fn overlap() {
+        let world = world("[legend]\n# = block body\no = body; body_shape = ball\n[grid]\n#.o\n");
+        let (wall, ball) = (entity_at(&world, 0, 0), entity_at(&world, 2, 0));
+        let shape = WolfShape::ball(0.5);
+        // touching the wall and the ball from the tile between them
+        assert_eq!(world.overlap(&shape, Vec2::new(1.5, 0.5), WolfQueryFilter::default()), vec![]);
+        assert_eq!(world.overlap(&shape, Vec2::new(1.4, 0.5), WolfQueryFilter::default()), vec![wall]);
+        assert_eq!(world.overlap(&shape, Vec2::new(1.6, 0.5), WolfQueryFilter::default()), vec![ball]);
+        let mut both = world.overlap(&WolfShape::cuboid(Vec2::new(0.6, 0.5)), Vec2::new(1.5, 0.5), WolfQueryFilter::default());
+        both.sort();
+        assert_eq!(both, vec![wall, ball]);
+        assert_eq!(world.overlap(&shape, Vec2::new(1.6, 0.5), WolfQueryFilter::default().excluding(ball)), vec![]);
+    }
+
+    #[test]
+    fn sweep() {
+        let mut world = world("[legend]\n# = block body\nD = door body\n[grid]\n#..D..#\n");
+        let (wall, door) = (entity_at(&world, 0, 0), entity_at(&world, 3, 0));
+        let shape = WolfShape::ball(0.25);
+
+        let hit = world.sweep(&shape, Vec2::new(2.5, 0.5), Vec2::new(0.5, 0.5), WolfQueryFilter::default()).unwrap();
+        assert_eq!(hit.entity, wall);
+        // the ball touches the wall after moving 1.25 of 2 tiles
+        assert!((hit.toi - 0.625).abs() < 1e-5 && hit.position.distance(Vec2::new(1.25, 0.5)) < 1e-5, "{:?}", hit);
+        assert!(hit.normal.distance(Vec2::X) < 1e-5 && (hit.depth - 0.75).abs() < 1e-5, "{:?}", hit);
+        assert!(world.sweep(&shape, Vec2::new(2.5, 0.5), Vec2::new(1.5, 0.5), WolfQueryFilter::default()).is_none());
+        // touching the wall, moving away and along it
+        assert!(world.sweep(&shape, Vec2::new(1.25, 0.5), Vec2::new(2.0, 0.5), WolfQueryFilter::default()).is_none());
+        assert!(world.sweep(&shape, Vec2::new(1.25, 0.5), Vec2::new(1.25, 0.9), WolfQueryFilter::default()).is_none());
+
+        let hit = world.sweep(&shape, Vec2::new(1.5, 0.5), Vec2::new(5.5, 0.5), WolfQueryFilter::default()).unwrap();
+        assert_eq!(hit.entity, door);
+        assert!((hit.toi - 0.3125).abs() < 1e-5 && hit.normal.distance(Vec2::NEG_X) < 1e-5, "{:?}", hit);
+
+        // an open door is passed through
+        let mut body = world.grid.body(door).unwrap().clone();
+        body.disabled = true;
+        world.grid.insert_body(door, body);
+        let hit = world.sweep(&shape, Vec2::new(1.5, 0.5), Vec2::new(6.5, 0.5), WolfQueryFilter::default()).unwrap();
+        assert_eq!(hit.entity, entity_at(&world, 6, 0));
+        assert!((hit.toi - 0.85).abs() < 1e-5, "{:?}", hit);
+    }
+
+    #[test]
+    fn move_and_slide() {
+        let world = world("[legend]\n# = block body\n[grid]\n#####\n#...#\n#...#\n#####\n");
+        let shape = WolfShape::ball(0.25);
+        let filter = WolfQueryFilter::default();
+
+        let moved = world.move_and_slide(&shape, Vec2::new(2.5, 1.5), Vec2::new(0.5, 0.25), filter);
+        assert_eq!((moved.position, moved.remaining, moved.hits.len()), (Vec2::new(3.0, 1.75), Vec2::ZERO, 0));
+
+        // straight into the west wall, which blocks what is left of the motion
+        let moved = world.move_and_slide(&shape, Vec2::new(2.5, 1.5), Vec2::new(-2.0, 0.0), filter);
+        assert!(moved.position.distance(Vec2::new(1.25, 1.5)) < 0.01, "{:?}", moved);
+        assert!(moved.remaining.distance(Vec2::new(-0.75, 0.0)) < 0.01, "{:?}", moved);
+        assert_eq!(moved.hits.len(), 1);
+
+        // sliding north along the wall keeps the motion along it
+        let moved = world.move_and_slide(&shape, Vec2::new(2.5, 1.5), Vec2::new(-2.0, 0.5), filter);
+        assert!(moved.position.distance(Vec2::new(1.25, 2.0)) < 0.01, "{:?}", moved);
+        assert!(moved.remaining.distance(Vec2::new(-0.75, 0.0)) < 0.01, "{:?}", moved);
+
+        // into the south west corner
+        let moved = world.move_and_slide(&shape, Vec2::new(2.5, 2.5), Vec2::new(-2.0, -2.0), filter);
+        assert!(moved.position.distance(Vec2::new(1.25, 1.25)) < 0.01, "{:?}", moved);
+        assert!(moved.remaining.distance(Vec2::new(-0.75, -0.75)) < 0.01, "{:?}", moved);
+        assert_eq!(moved.remaining, Vec2::new(-2.0, -2.0) - (moved.position - Vec2::new(2.5, 2.5)));
+    }
+}
@@ -1,15 +1,18 @@
 use std::f32::consts::PI;
 
 use crate::{
-    assets::WolfMap,
+    assets::{WolfMap, WolfMapKey},
     components::{Spawn, Timer, WolfCamera, WolfUIFPSText},
-    AssetMap, DoorState, Prev, WolfPush, WolfAssets, WolfBody, WolfConfig, WolfDoor, WolfEntity,
+    AssetMap, DoorState, Prev, WolfPush, WolfAssets, WolfBody, WolfClassRegistry, InsertWolfComponents, WolfConfig, WolfDoor, WolfEntity,
     WolfEntityRef, WolfInstance, WolfInstanceManager, WolfInteract, WolfInteractEvent, WolfSprite,
-    WolfWorld, BODY_SHAPE_BALL, BODY_SHAPE_CUBOID, WolfDynamicBlock, WolfNorth, WolfSouth, WolfWest,
+    WolfAction, WolfCollisionEvent, WolfController, WolfInput, WolfInputMap, WolfInputSource, WolfMapGeometry, WolfMove, WolfMapLoadedEvent, WolfPlayer, WolfQueryFilter, WolfSet, WolfStatic, WolfTileGrid, WolfTrigger, WolfTriggerEnter, WolfTriggerExit, WolfVelocity, WolfViewEffects, WolfFlinchEvent, WolfShakeEvent, WolfDoorDeniedEvent, WolfInventory, WolfKey, WolfKeyPickupEvent,
+    resources::BODY_REACH, WolfWorld, WolfDynamicBlock, WolfNorth, WolfSouth, WolfWest,
 };
 
 use bevy::{
+    asset::AssetLoadFailedEvent,
     input::mouse::MouseMotion,
+    transform::TransformSystem,
     prelude::*,
     render::{
         mesh::{Indices, VertexAttributeValues},
@@ -17,7 +20,6 @@ use bevy::{
     },
     utils::{HashMap},
 };
-use parry2d::{bounding_volume::BoundingVolume, query::RayCast, na::ComplexField};
 
 pub fn startup_system(
     mut commands: Commands,
@@ -68,20 +70,12 @@ fn ui_system(
 pub fn spawn_system(
     mut commands: Commands,
     spawns: Query<(Entity, &WolfEntity), Added<WolfEntity>>,
-    ass: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    world: ResMut<WolfWorld>,
-    mut assets: ResMut<WolfAssets>,
+    registry: Res<WolfClassRegistry>,
 ) {
-    let block_mesh: Handle<Mesh> = ass.load("meshes/block.gltf#Mesh0/Primitive0");
-    let mut existing_materials = HashMap::new();
-    for (id, material) in materials.iter() {
-        existing_materials.insert(material.base_color_texture.clone(), id.clone());
-    }
     for (e, we) in spawns.iter() {
-        let mut transform = Transform::from_xyz(we.start_pos.x, we.start_pos.y, we.start_pos.z)
-            .looking_to(Vec3::new(1.0, 0.0, 0.0), Vec3::Z);
+        let facing = Vec2::from_angle(we.start_facing.to_radians());
+        let transform = Transform::from_xyz(we.start_pos.x, we.start_pos.y, we.start_pos.z)
+            .looking_to(facing.extend(0.0), Vec3::Z);
         let mut entity = commands.entity(e);
         entity.insert(SpatialBundle {
             transform,
@@ -91,163 +85,51 @@ pub fn spawn_system(
             component: transform,
         });
 
-        let image = we.get_property_string("image");
-        if we.has_class("camera") {
-            entity
-                .insert(Camera3dBundle {
-                    transform,
-                    ..Default::default()
-                })
-                .insert(WolfCamera::default());
-        }
-
-        if we.has_class("block") {
-            if let Some(image) = image {
-                let img_handle = ass.load(image);
-                let material = match assets.standard_materials.get(&image) {
-                    Some(material) => material,
-                    None => {
-                        let material = materials.add(StandardMaterial {
-                            perceptual_roughness: 1.0,
-                            metallic: 0.0,
-                            base_color_texture: Some(img_handle),
-                            unlit: true,
-                            ..Default::default()
-                        });
-                        assets.standard_materials.insert(&image, material.clone());
-                        material
-                    },
-                };
-               
-                entity
-                    .insert(WolfInstance {
-                        mesh: block_mesh.clone(),
-                        material,
-                        request_redraw: true,
-                        ..Default::default()
-                    })
-                    .insert(transform);
+        commands.add(InsertWolfComponents { entity: e });
+        for (class, handler) in registry.iter() {
+            if we.has_class(class) {
+                commands.run_system_with_input(handler, e);
             }
         }
+    }
+}
 
-        if we.has_class("sprite") {
-            let atlas_width = *we.get_property_int("atlas_width").unwrap_or(&1) as u8;
-            let atlas_height = *we.get_property_int("atlas_height").unwrap_or(&1) as u8;
-            let atlas = assets
-                .sprite_meshes
-                .get(atlas_height, atlas_width, &mut meshes);
-            entity.insert(WolfSprite {
-                atlas_height,
-                atlas_width,
-                ..Default::default()
-            });
-            let id = entity.id();
-            entity
-                .commands()
-                .spawn(PbrBundle {
-                    mesh: atlas.index(0),
-                    material: materials.add(StandardMaterial {
-                        alpha_mode: AlphaMode::Blend,
-                        perceptual_roughness: 1.0,
-                        metallic: 0.0,
-                        cull_mode: None,
-                        base_color_texture: image.and_then(|x| Some(ass.load(x))),
-                        unlit: true,
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                })
-                .insert(WolfEntityRef { entity: id });
-        }
-
-        if we.has_class("door") {
-            let right = we.start_pos.as_uvec3().truncate() + UVec2::X;
-            let tiles = world.map.get(right);
-            for tile in tiles {
-                if tile.has_class("block") {
-                    transform.look_to(Vec3::Y, Vec3::Z)
-                }
-            }
-
-            entity.insert(SpatialBundle {
-                transform,
-                ..Default::default()
-            });
-
-            entity.insert(WolfDoor {
-                ..Default::default()
-            });
-
-            entity.with_children(|mut builder| {
-                builder.spawn(PbrBundle {
-                    mesh: assets.sprite_meshes.get(1, 1, &mut meshes).index(0),
-                    material: materials.add(StandardMaterial {
-                        alpha_mode: AlphaMode::Blend,
-                        perceptual_roughness: 1.0,
-                        metallic: 0.0,
-                        cull_mode: None,
-                        base_color_texture: image.and_then(|x| Some(ass.load(x))),
-                        unlit: true,
-                        ..Default::default()
-                    }),
-                    transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                    ..Default::default()
-                });
-            });
-        }
-
-        if we.has_class("body") {
-            let radius = we.properties_float.get("body_radius").unwrap_or(&0.5);
-            let height = we.properties_float.get("body_height").unwrap_or(&1.0);
-            let shape = we.properties_int.get("body_shape").unwrap_or(&0);
-            let body = WolfBody {
-                height: *height,
-                radius: *radius,
-                shape: *shape as u8,
-                ..Default::default()
-            };
-            entity.insert(body);
-        }
-
-        if we.has_class("interact") {
-            entity.insert(WolfInteract {
-                ..Default::default()
-            });
-        }
-
-        if we.has_class("push") {
-            for (other_e, we2) in spawns
-                .iter()
-                .filter(|(_, we2)| we2.start_pos == we.start_pos)
-            {
-                if we2.has_class("block") {
-                    commands
-                        .entity(other_e)
-                        .insert(WolfInteract::default())
-                        .insert(WolfPush::default());
-                }
-            }
-        }
+/// Interpolates between the previous and the current transform of the simulation,
+/// where `alpha` is how far the current time is into the next simulation tick.
+pub fn interpolate(prev: &Transform, current: &Transform, alpha: f32) -> Transform {
+    Transform {
+        translation: prev.translation.lerp(current.translation, alpha),
+        rotation: prev.rotation.slerp(current.rotation, alpha),
+        scale: prev.scale.lerp(current.scale, alpha),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sprite_system(
     mut commands: Commands,
     mut sprite_meshes: Query<(Entity, &mut Handle<Mesh>, &WolfEntityRef)>,
     mut transforms: Query<&mut Transform>,
+    prev_transforms: Query<&Prev<Transform>>,
     sprites: Query<(Entity, &WolfSprite)>,
     cameras: Query<Entity, With<Camera3d>>,
+    time: Res<Time<Fixed>>,
     mut assets: ResMut<WolfAssets>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
+    let alpha = time.overstep_fraction();
+    let interpolated = |e: Entity, transforms: &Query<&mut Transform>| {
+        let current = *transforms.get(e).unwrap_or(&Transform::default());
+        match prev_transforms.get(e) {
+            Ok(prev) => interpolate(prev, &current, alpha),
+            Err(_) => current,
+        }
+    };
     for camera in cameras.iter() {
-        let camera_transform = transforms.get(camera).unwrap().clone();
+        let camera_transform = interpolated(camera, &transforms);
         for (e, mut mesh_handle, r) in sprite_meshes.iter_mut() {
             match sprites.get(r.entity) {
                 Ok((sprite_entity, sprite)) => {
-                    let sprite_transform = *transforms
-                        .get(sprite_entity)
-                        .unwrap_or(&Transform::default());
+                    let sprite_transform = interpolated(sprite_entity, &transforms);
                     if let Ok(mut transform) = transforms.get_mut(e) {
                         transform.translation = sprite_transform.translation;
                         let atlas = assets.sprite_meshes.get(
@@ -274,8 +156,8 @@ fn load_map_system(
     mut world: ResMut<WolfWorld>,
     maps: Res<Assets<WolfMap>>,
     entities: Query<Entity, With<WolfEntity>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<(Entity, &WolfCamera)>,
+    mut loaded: EventWriter<WolfMapLoadedEvent>,
 ) {
     let Some(handle) = &world.map_handle else {
         return;
@@ -289,22 +171,42 @@ fn load_map_system(
     for e in entities.iter() {
         commands.entity(e).despawn_recursive();
     }
-    world.map = wolf_map.clone();
-    world.map_handle = None;
-
-    let map = &world.map;
-    for layer in map.layers.iter() {
-        for y in 0..map.height {
-            for x in 0..map.width {
-                if let Some(wolf_entity) = layer.get(y as usize, x as usize).unwrap() {
-                    commands.spawn(wolf_entity.clone());
-                }
-            }
+    for (e, camera) in cameras.iter() {
+        if camera.target.is_some_and(|target| entities.contains(target)) {
+            commands.entity(e).despawn_recursive();
         }
     }
-    let size = wolf_map.height.max(wolf_map.width) as f32;
+    world.map = wolf_map.clone();
+    world.tiles = WolfTileGrid::from_map(&world.map);
+    world.current_map_handle = world.map_handle.take();
+
+    let mut spawned = HashMap::new();
+    for (key, wolf_entity) in world.map.entities() {
+        spawned.insert(key, commands.spawn(wolf_entity.clone()).id());
+    }
+    world.entities = spawned;
+    loaded.send(WolfMapLoadedEvent);
+}
+
+/// Spawns the floor and ceiling of a newly loaded map.
+fn map_geometry_system(
+    mut commands: Commands,
+    mut loaded: EventReader<WolfMapLoadedEvent>,
+    world: Res<WolfWorld>,
+    geometry: Query<Entity, With<WolfMapGeometry>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if loaded.read().count() == 0 {
+        return;
+    }
+    for e in geometry.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let size = world.map.height.max(world.map.width) as f32;
     // spawn floor
-    commands.spawn(PbrBundle {
+    commands.spawn((WolfMapGeometry, PbrBundle {
         mesh: meshes.add(Mesh::from(shape::Plane::from_size(size))),
         transform: Transform::from_xyz(size / 2.0, size / 2.0, 0.0).looking_to(Vec3::Y, Vec3::Z),
         material: materials.add(StandardMaterial {
@@ -315,10 +217,10 @@ fn load_map_system(
             ..Default::default()
         }),
         ..Default::default()
-    });
+    }));
 
     // spawn cealing
-    commands.spawn(PbrBundle {
+    commands.spawn((WolfMapGeometry, PbrBundle {
         mesh: meshes.add(Mesh::from(shape::Plane::from_size(64.0))),
         transform: Transform::from_xyz(size / 2.0, size / 2.0, 1.0).looking_to(Vec3::Y, -Vec3::Z),
         material: materials.add(StandardMaterial {
@@ -329,27 +231,202 @@ fn load_map_system(
             ..Default::default()
         }),
         ..Default::default()
-    });
+    }));
+}
+
+fn map_load_failed_system(
+    mut world: ResMut<WolfWorld>,
+    mut failed: EventReader<AssetLoadFailedEvent<WolfMap>>,
+) {
+    for ev in failed.read() {
+        let Some(handle) = &world.map_handle else {
+            continue;
+        };
+        if handle.id() == ev.id {
+            // keep the current map running, the error has already been logged by the asset server
+            warn!("failed to load map '{}', keeping current map", ev.path);
+            world.map_handle = None;
+        }
+    }
+}
+
+/// Whether the entity is a player or camera, which hot reloading leaves in place.
+fn is_player(wolf_entity: &WolfEntity) -> bool {
+    wolf_entity.has_class("player") || wolf_entity.has_class("camera")
+}
+
+/// Applies changes made to the current map in place, respawning only the tiles and objects
+/// which changed, while leaving the players and the runtime state of everything else untouched.
+fn hot_reload_map_system(
+    mut commands: Commands,
+    mut world: ResMut<WolfWorld>,
+    mut events: EventReader<AssetEvent<WolfMap>>,
+    maps: Res<Assets<WolfMap>>,
+) {
+    let mut modified = false;
+    for ev in events.read() {
+        if let AssetEvent::Modified { id } = ev {
+            if world.current_map_handle.as_ref().map(|h| h.id()) == Some(*id) {
+                modified = true;
+            }
+        }
+    }
+    if !modified {
+        return;
+    }
+    let Some(wolf_map) = world.current_map_handle.as_ref().and_then(|h| maps.get(h)) else {
+        return;
+    };
+
+    let mut entities = world.entities.clone();
+    {
+        let old: HashMap<WolfMapKey, &WolfEntity> = world.map.entities().collect();
+        let new: HashMap<WolfMapKey, &WolfEntity> = wolf_map.entities().collect();
+        for (key, wolf_entity) in old.iter() {
+            if is_player(wolf_entity) || new.get(key) == Some(wolf_entity) {
+                continue;
+            }
+            if let Some(e) = entities.remove(key) {
+                commands.entity(e).despawn_recursive();
+            }
+        }
+        for (key, wolf_entity) in new.iter() {
+            if is_player(wolf_entity) || entities.contains_key(key) {
+                continue;
+            }
+            entities.insert(*key, commands.spawn((*wolf_entity).clone()).id());
+        }
+    }
+
+    world.map = wolf_map.clone();
+    world.tiles = WolfTileGrid::from_map(&world.map);
+    world.entities = entities;
 }
 
-pub fn camera_system(
-    mut cameras: Query<(&mut Transform, &mut WolfEntity), With<WolfCamera>>,
+/// Gathers the input of the frame for the fixed simulation ticks to consume,
+/// as a frame can have any number of ticks.
+#[allow(clippy::too_many_arguments)]
+fn input_system(
+    mut input: ResMut<WolfInput>,
+    map: Res<WolfInputMap>,
     keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    players: Query<&WolfPlayer>,
+) {
+    let mouse = mouse_motion.read().fold(Vec2::ZERO, |sum, ev| sum + ev.delta);
+    // `Gamepads` iterates in hash order, so sort them for every player to keep the same gamepad
+    let mut gamepads: Vec<Gamepad> = gamepads.iter().collect();
+    gamepads.sort_by_key(|gamepad| gamepad.id);
+    let count = players
+        .iter()
+        .map(|p| p.index + 1)
+        .chain([1, gamepads.len()])
+        .max()
+        .unwrap_or(1);
+    input.players.resize_with(count, Default::default);
+
+    let mut held: HashMap<WolfAction, (f32, bool)> = HashMap::new();
+    for (index, actions) in input.players.iter_mut().enumerate() {
+        // the keyboard and mouse drive the first player, gamepads one player each
+        let keyboard = index == 0;
+        let gamepad = gamepads.get(index).copied();
+        held.clear();
+        if map.is_changed() {
+            actions.forget_unbound(&map);
+        }
+        for binding in map.bindings.iter() {
+            let button = |pressed: bool, just_pressed: bool| (pressed as u8 as f32, just_pressed);
+            let (value, tapped) = match binding.source {
+                WolfInputSource::Key(key) if keyboard => button(keys.pressed(key), keys.just_pressed(key)),
+                WolfInputSource::MouseButton(b) if keyboard => {
+                    button(mouse_buttons.pressed(b), mouse_buttons.just_pressed(b))
+                }
+                WolfInputSource::MouseX if keyboard => {
+                    actions.add_motion(binding.action, mouse.x * binding.scale);
+                    continue;
+                }
+                WolfInputSource::MouseY if keyboard => {
+                    actions.add_motion(binding.action, mouse.y * binding.scale);
+                    continue;
+                }
+                WolfInputSource::GamepadButton(button_type) => {
+                    let Some(gamepad) = gamepad else {
+                        continue;
+                    };
+                    let b = GamepadButton::new(gamepad, button_type);
+                    button(gamepad_buttons.pressed(b), gamepad_buttons.just_pressed(b))
+                }
+                WolfInputSource::GamepadAxis(axis_type) => {
+                    let Some(gamepad) = gamepad else {
+                        continue;
+                    };
+                    (gamepad_axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.0), false)
+                }
+                _ => continue,
+            };
+            let entry = held.entry(binding.action).or_default();
+            entry.0 += value * binding.scale;
+            entry.1 |= tapped;
+        }
+        for action in map.bindings.iter().map(|b| b.action) {
+            let (value, tapped) = held.get(&action).copied().unwrap_or_default();
+            actions.hold(action, value.clamp(-1.0, 1.0), tapped);
+        }
+    }
+}
+
+/// Clears the input consumed by a simulation tick.
+fn consume_input_system(mut input: ResMut<WolfInput>) {
+    for actions in input.players.iter_mut() {
+        actions.consume();
+    }
+}
+
+fn tick_rate_system(config: Res<WolfConfig>, mut time: ResMut<Time<Fixed>>) {
+    if config.is_changed() {
+        time.set_timestep_hz(config.tick_rate);
+    }
+}
+
+/// Turns the players and sets the direction their controllers move in from the input.
+pub fn player_system(
+    mut players: Query<(&mut Transform, &WolfPlayer, Option<&mut WolfController>)>,
+    input: Res<WolfInput>,
     time: Res<Time>,
     config: Res<WolfConfig>,
-    mut mouse_motion: EventReader<MouseMotion>,
 ) {
-    for (mut transform, mut we) in cameras.iter_mut() {
-        let mut v = Vec3::new(0.0, 0.0, 0.0);
+    let dt = time.delta_seconds();
+    for (mut transform, player, controller) in players.iter_mut() {
+        let Some(actions) = input.player(player.index) else {
+            continue;
+        };
         let up = Vec3::new(0.0, 0.0, 1.0);
 
-        // turn
-        for ev in mouse_motion.read() {
-            transform.rotate_z(-ev.delta.x * config.turn_speed);
+        // turn, by how far the mouse moved and by how far a key or stick is held,
+        // where holding the strafe modifier strafes instead of turning with held inputs
+        let strafing = actions.pressed(WolfAction::StrafeModifier);
+        let held_turn = if strafing { 0.0 } else { actions.value(WolfAction::Turn) };
+        let mut look = Vec2::new(
+            actions.motion(WolfAction::Turn) * config.turn_speed + held_turn * config.turn_rate * dt,
+            actions.motion(WolfAction::Look) * config.turn_speed + actions.value(WolfAction::Look) * config.turn_rate * dt,
+        );
+        if config.lock_pitch {
+            look.y = 0.0;
+            let forward = transform.forward().truncate();
+            if transform.forward().z.abs() > f32::EPSILON && forward != Vec2::ZERO {
+                transform.look_to(forward.extend(0.0), Vec3::Z);
+            }
+        }
+        if look != Vec2::ZERO {
+            transform.rotate_z(-look.x);
             let forward = transform.forward().normalize_or_zero();
             let side = forward.cross(up);
             let mut t = transform.clone();
-            t.rotate_axis(side.normalize_or_zero(), -ev.delta.y * config.turn_speed);
+            t.rotate_axis(side.normalize_or_zero(), look.y);
             let side = t.forward().normalize_or_zero().cross(up);
             if side.length() > 0.1 {
                 *transform = t;
@@ -359,64 +436,172 @@ pub fn camera_system(
         }
 
         // movement
-        if keys.pressed(config.forward_key) {
-            v.y = 1.0;
-        }
-        if keys.pressed(config.backward_key) {
-            v.y -= 1.0;
+        let mut strafe = actions.value(WolfAction::Strafe);
+        if strafing {
+            strafe += actions.value(WolfAction::Turn);
         }
-        if keys.pressed(config.strife_left_key) {
-            v.x -= 1.0;
+        let mut v = Vec2::new(strafe, actions.value(WolfAction::MoveForward)).clamp_length_max(1.0);
+        if actions.pressed(WolfAction::Run) {
+            v *= config.run_multiplier;
         }
-        if keys.pressed(config.strife_right_key) {
-            v.x += 1.0;
-        }
-        let v = v.normalize_or_zero();
         let forward = transform.forward();
         let side = forward.cross(up).normalize_or_zero();
-        let dt = time.delta_seconds();
-        let speed = 10.0;
-        transform.translation += forward * v.y * dt * speed;
-        transform.translation += side * v.x * dt * speed;
-        we.start_pos = transform.translation;
-        break;
+        // walking does not depend on looking up or down
+        let forward = forward.truncate().normalize_or_zero();
+        if let Some(mut controller) = controller {
+            controller.wish = forward * v.y + side.truncate() * v.x;
+        }
     }
 }
 
 fn interactor_system(
-    mut cameras: Query<(Entity, &mut Transform, &mut WolfEntity), With<WolfCamera>>,
-    interacts: Query<(&Transform, &WolfBody, &WolfInteract), Without<WolfCamera>>,
-    keys: Res<ButtonInput<KeyCode>>,
-    config: Res<WolfConfig>,
+    players: Query<(Entity, &Transform, &WolfPlayer)>,
+    interacts: Query<&WolfInteract>,
+    input: Res<WolfInput>,
     world: Res<WolfWorld>,
     mut writer: EventWriter<WolfInteractEvent>,
 ) {
-    for (camera_entity, transform, we) in cameras.iter_mut() {
-        if keys.just_pressed(config.interaction_key) {
-            let v = transform.forward(); //transform.rotation * Vec3::new(1.0, 0.0, 0.0);
-            let ray = parry2d::query::Ray::new(
-                [transform.translation.x, transform.translation.y].into(),
-                [v.x, v.y].into(),
-            );
-            let l = 1.0;
-            for (e, p) in world
-                .grid
-                .query_around(transform.translation.truncate(), 2.0)
-            {
-                let Ok((_, wb, _wi)) = interacts.get(e) else {
-                    continue;
-                };
-                let s = parry2d::shape::Cuboid::new([wb.radius, wb.radius].into());
-                if let Some(_) = s.cast_ray_and_get_normal(&[p.x, p.y].into(), &ray, l, true) {
-                    writer.send(WolfInteractEvent {
-                        interactor: camera_entity,
-                        entity: e,
-                    });
+    for (player_entity, transform, player) in players.iter() {
+        if !input.player(player.index).is_some_and(|actions| actions.just_pressed(WolfAction::Use)) {
+            continue;
+        }
+        let filter = WolfQueryFilter::default().excluding(player_entity);
+        let hit = world.raycast(
+            transform.translation.truncate(),
+            transform.forward().truncate(),
+            1.0,
+            filter,
+        );
+        if let Some(hit) = hit.filter(|hit| interacts.contains(hit.entity)) {
+            writer.send(WolfInteractEvent {
+                interactor: player_entity,
+                entity: hit.entity,
+            });
+        }
+    }
+}
+
+/// Moves the cameras to the eyes of their targets, looking the way the targets face.
+fn camera_follow_system(
+    mut cameras: Query<(&WolfCamera, &mut Transform)>,
+    targets: Query<&Transform, Without<WolfCamera>>,
+) {
+    for (camera, mut transform) in cameras.iter_mut() {
+        let Some(target) = camera.target.and_then(|target| targets.get(target).ok()) else {
+            continue;
+        };
+        transform.translation = target.translation.truncate().extend(camera.eye_height);
+        transform.rotation = target.rotation;
+    }
+}
+
+/// Bobs, rolls, kicks and shakes the views of the cameras following a target, settling the
+/// effects each frame, which `apply_view_effects_system` then puts on top of the eyes.
+#[allow(clippy::type_complexity)]
+fn view_effects_system(
+    mut cameras: Query<(&WolfCamera, &mut WolfViewEffects, &Transform)>,
+    targets: Query<(&Transform, Option<&WolfVelocity>, Option<&WolfController>), Without<WolfCamera>>,
+    mut flinches: EventReader<WolfFlinchEvent>,
+    mut shakes: EventReader<WolfShakeEvent>,
+    time: Res<Time>,
+    config: Res<WolfConfig>,
+) {
+    let flinches: Vec<WolfFlinchEvent> = flinches.read().copied().collect();
+    let shakes: Vec<WolfShakeEvent> = shakes.read().copied().collect();
+    let dt = time.delta_seconds();
+    let settle = 1.0 - (-config.view_recovery * dt).exp();
+    for (camera, mut effects, transform) in cameras.iter_mut() {
+        // cameras without a target have no eyes to apply the effects on top of
+        let Some(target) = camera.target else {
+            continue;
+        };
+        let Ok((target_transform, velocity, controller)) = targets.get(target) else {
+            continue;
+        };
+
+        for flinch in flinches.iter().filter(|flinch| flinch.entity == target) {
+            effects.flinch += config.flinch.to_radians() * flinch.amount;
+        }
+        for shake in shakes.iter() {
+            let distance = transform.translation.truncate().distance(shake.position);
+            let falloff = (1.0 - distance / shake.radius.max(f32::EPSILON)).max(0.0);
+            effects.trauma = (effects.trauma + shake.strength * falloff).min(1.0);
+        }
+
+        // how fast the target moves forward and sideways, relative to walking
+        let velocity = velocity.map_or(Vec2::ZERO, |v| v.linear);
+        let forward = target_transform.forward().truncate().normalize_or_zero();
+        let right = Vec2::new(forward.y, -forward.x);
+        let walk_speed = controller.map_or(config.move_speed, |controller| controller.speed);
+        let walk = |speed: f32| (speed / walk_speed.max(f32::EPSILON)).clamp(-1.0, 1.0);
+
+        effects.bob_phase += velocity.length() * dt;
+        let bob = config.head_bob * walk(velocity.length());
+        effects.bob += (bob - effects.bob) * settle;
+        let roll = config.view_roll.to_radians() * walk(velocity.dot(right));
+        effects.roll += (roll - effects.roll) * settle;
+        effects.flinch -= effects.flinch * settle;
+        effects.trauma -= effects.trauma * settle;
+    }
+}
+
+/// Puts the view effects on top of the interpolated eyes of the cameras, leaving their
+/// `Transform` to the simulation.
+#[allow(clippy::type_complexity)]
+fn apply_view_effects_system(
+    mut cameras: Query<(&WolfCamera, &WolfViewEffects, &Transform, Option<&Prev<Transform>>, &mut GlobalTransform)>,
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    config: Res<WolfConfig>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    let t = time.elapsed_seconds();
+    for (camera, effects, current, prev, mut global_transform) in cameras.iter_mut() {
+        if camera.target.is_none() {
+            continue;
+        }
+        let mut transform = match prev {
+            Some(prev) => interpolate(prev, current, alpha),
+            None => *current,
+        };
+        let bob_angle = effects.bob_phase / config.head_bob_length.max(f32::EPSILON) * std::f32::consts::TAU;
+        transform.translation.z += effects.bob * bob_angle.sin();
+        // shaking grows with the square of the trauma, so small shakes stay subtle
+        let shake = config.shake * effects.trauma * effects.trauma;
+        if shake > 0.0 {
+            let offset = Vec2::new((t * 37.0).sin(), (t * 53.0 + 1.3).sin()) * shake;
+            let (right, up) = (transform.right(), transform.up());
+            transform.translation += right * offset.x + up * offset.y;
+        }
+        transform.rotate_local_x(effects.flinch);
+        transform.rotate_local_z(-effects.roll);
+        *global_transform = transform.into();
+    }
+}
+
+/// Renders the world from newly spawned cameras, keeping their projections up to date.
+fn camera_presentation_system(
+    mut commands: Commands,
+    mut cameras: Query<(Entity, &WolfCamera, &Transform, Option<&mut Projection>), Changed<WolfCamera>>,
+) {
+    for (e, camera, transform, projection) in cameras.iter_mut() {
+        match projection {
+            Some(mut projection) => {
+                if let Projection::Perspective(perspective) = projection.as_mut() {
+                    perspective.fov = camera.fov.to_radians();
                 }
             }
+            None => {
+                commands.entity(e).insert(Camera3dBundle {
+                    transform: *transform,
+                    projection: Projection::Perspective(PerspectiveProjection {
+                        fov: camera.fov.to_radians(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                });
+            }
         }
-
-        break;
     }
 }
 
@@ -459,7 +644,7 @@ pub fn instance_manager_spawn_system(
 
 pub fn instance_manage_render_system(
     mut commands: Commands,
-    mut instances: Query<(&mut WolfInstance<StandardMaterial>, &mut Transform)>,
+    mut instances: Query<(&mut WolfInstance<StandardMaterial>, Ref<GlobalTransform>)>,
     mut instance_managers: Query<(
         Entity,
         &mut WolfInstanceManager<StandardMaterial>,
@@ -575,31 +760,140 @@ pub fn debug_gizmos_system(
     }
 }
 
+/// Keeps the `WolfGrid` up to date with the entities that were spawned, moved or despawned.
+///
+/// Static bodies are resolved against the `WolfTileGrid` instead, so they never enter the grid.
+/// The grid is rebuilt from scratch instead if `WolfConfig::rebuild_grid` is set.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn spatial_hash_system(
     mut world: ResMut<WolfWorld>,
-    entities: Query<(Entity, &WolfEntity, &Transform)>,
-    time: Res<Time>,
+    changed: Query<
+        (Entity, &Transform),
+        (With<WolfEntity>, Without<WolfStatic>, Or<(Added<WolfEntity>, Changed<Transform>)>),
+    >,
+    changed_bodies: Query<(Entity, &WolfBody), (Without<WolfStatic>, Changed<WolfBody>)>,
+    entities: Query<(Entity, &Transform, Option<&WolfBody>), (With<WolfEntity>, Without<WolfStatic>)>,
+    added_statics: Query<Entity, Added<WolfStatic>>,
+    mut removed: RemovedComponents<WolfEntity>,
+    mut removed_bodies: RemovedComponents<WolfBody>,
+    mut removed_statics: RemovedComponents<WolfStatic>,
+    config: Res<WolfConfig>,
 ) {
-    world.grid.clear();
-    for (e, we, t) in entities.iter() {
+    if config.rebuild_grid {
+        removed.clear();
+        removed_bodies.clear();
+        removed_statics.clear();
+        world.grid.clear();
+        for (e, t, body) in entities.iter() {
+            world.grid.insert_or_replace(e, t.translation.truncate());
+            if let Some(body) = body {
+                world.grid.insert_body(e, body.clone());
+            }
+        }
+        return;
+    }
+    for e in removed.read() {
+        world.grid.remove(e);
+    }
+    for e in removed_bodies.read() {
+        world.grid.remove_body(e);
+    }
+    for e in added_statics.iter() {
+        world.grid.remove(e);
+    }
+    for e in removed_statics.read() {
+        if let Ok((_, t, body)) = entities.get(e) {
+            world.grid.insert_or_replace(e, t.translation.truncate());
+            if let Some(body) = body {
+                world.grid.insert_body(e, body.clone());
+            }
+        }
+    }
+    for (e, t) in changed.iter() {
         world.grid.insert_or_replace(e, t.translation.truncate());
     }
+    for (e, body) in changed_bodies.iter() {
+        world.grid.insert_body(e, body.clone());
+    }
+}
+
+/// Renders the entities of the simulation in between ticks.
+///
+/// Only the `GlobalTransform` is interpolated, so the `Transform` remains the state of the simulation.
+/// Children are placed relative to the interpolated parent, as they were propagated from the current one.
+#[allow(clippy::type_complexity)]
+pub fn interpolation_system(
+    roots: Query<(Entity, &Transform, &Prev<Transform>, Option<&Children>), Without<Parent>>,
+    children: Query<(&Transform, Option<&Children>), With<Parent>>,
+    mut global_transforms: Query<&mut GlobalTransform>,
+    time: Res<Time<Fixed>>,
+) {
+    let alpha = time.overstep_fraction();
+    for (e, current, prev, root_children) in roots.iter() {
+        let interpolated = if prev.component == *current {
+            GlobalTransform::from(*current)
+        } else {
+            GlobalTransform::from(interpolate(prev, current, alpha))
+        };
+        let Ok(mut global_transform) = global_transforms.get_mut(e) else {
+            continue;
+        };
+        // entities at rest keep the transform they were propagated, without being marked as changed
+        if *global_transform == interpolated {
+            continue;
+        }
+        *global_transform = interpolated;
+        if let Some(root_children) = root_children {
+            interpolate_children(interpolated, root_children, &children, &mut global_transforms);
+        }
+    }
 }
 
-pub fn prev_system(mut transforms: Query<(&Transform, &mut Prev<Transform>)>) {
+fn interpolate_children(
+    parent: GlobalTransform,
+    parent_children: &Children,
+    children: &Query<(&Transform, Option<&Children>), With<Parent>>,
+    global_transforms: &mut Query<&mut GlobalTransform>,
+) {
+    for child in parent_children.iter() {
+        let Ok((transform, grandchildren)) = children.get(*child) else {
+            continue;
+        };
+        let global_transform = parent.mul_transform(*transform);
+        if let Ok(mut child_global_transform) = global_transforms.get_mut(*child) {
+            *child_global_transform = global_transform;
+        }
+        if let Some(grandchildren) = grandchildren {
+            interpolate_children(global_transform, grandchildren, children, global_transforms);
+        }
+    }
+}
+
+pub fn prev_system(mut transforms: Query<(&Transform, &mut Prev<Transform>)>, mut doors: Query<&mut WolfDoor>) {
     for (current, mut prev) in transforms.iter_mut() {
         prev.component = *current;
     }
+    for mut door in doors.iter_mut() {
+        door.prev_slide = door.slide();
+    }
 }
 
 pub fn body_system(
-    bodies: Query<(Entity, &WolfBody)>,
+    bodies: Query<(Entity, &WolfBody), Without<WolfStatic>>,
     mut transforms: Query<&mut Transform>,
     mut prev_transforms: Query<&Prev<Transform>>,
     mut world: ResMut<WolfWorld>,
+    controllers: Query<(), With<WolfController>>,
+    config: Res<WolfConfig>,
+    mut collision_writer: EventWriter<WolfCollisionEvent>,
 ) {
     let mut contacts = Vec::with_capacity(8);
+    let mut collisions: Vec<WolfCollisionEvent> = Vec::new();
     for (entity, body) in bodies.iter() {
+        // controlled bodies resolve their own collisions as they move
+        if controllers.contains(entity) {
+            continue;
+        }
         let Ok(transform) = transforms.get(entity) else {
             continue;
         };
@@ -641,46 +935,63 @@ pub fn body_system(
                     let Ok((_, other_body)) = bodies.get(other_e) else {
                         continue;
                     };
-                    if other_body.disabled {
+                    if other_body.disabled || !body.collides_with(other_body) {
                         continue;
                     }
-                    let a_cuboid = parry2d::shape::Cuboid::new([body.radius, body.radius].into());
-                    let a_ball = parry2d::shape::Ball::new(body.radius);
-                    let b_cuboid =
-                        parry2d::shape::Cuboid::new([other_body.radius, other_body.radius].into());
-                    let b_ball = parry2d::shape::Ball::new(other_body.radius);
                     if let Ok(Some(c)) = parry2d::query::contact(
                         &[e.x, e.y].into(),
-                        match body.shape {
-                            BODY_SHAPE_CUBOID => &a_cuboid as &dyn parry2d::shape::Shape,
-                            BODY_SHAPE_BALL => &a_ball as &dyn parry2d::shape::Shape,
-                            _ => &a_cuboid as &dyn parry2d::shape::Shape,
-                        },
+                        body.shape.as_shape(),
                         &[other_pos.x, other_pos.y].into(),
-                        match other_body.shape {
-                            BODY_SHAPE_CUBOID => &b_cuboid as &dyn parry2d::shape::Shape,
-                            BODY_SHAPE_BALL => &b_ball as &dyn parry2d::shape::Shape,
-                            _ => &a_cuboid as &dyn parry2d::shape::Shape,
-                        },
+                        other_body.shape.as_shape(),
                         0.0,
                     ) {
-                        contacts.push(c);
+                        contacts.push((other_e, c));
+                    }
+                }
+                if !body.disabled {
+                    let tile_shape = parry2d::shape::Cuboid::new([0.5, 0.5].into());
+                    let aabb = body.shape.as_shape().compute_aabb(&[e.x, e.y].into());
+                    let mins = Vec2::new(aabb.mins.x, aabb.mins.y);
+                    let maxs = Vec2::new(aabb.maxs.x, aabb.maxs.y);
+                    for (tile, _) in world.tiles.overlapping(mins, maxs) {
+                        let Some(other_e) = world.static_block(tile, &WolfQueryFilter::new(body.groups)) else {
+                            continue;
+                        };
+                        let center = tile.as_vec2() + 0.5;
+                        if let Ok(Some(c)) = parry2d::query::contact(
+                            &[e.x, e.y].into(),
+                            body.shape.as_shape(),
+                            &[center.x, center.y].into(),
+                            &tile_shape,
+                            0.0,
+                        ) {
+                            contacts.push((other_e, c));
+                        }
                     }
                 }
 
                 if contacts.len() > 0 {
-                    let mut contact = contacts[0];
+                    let (mut other_e, mut contact) = contacts[0];
                     let mut dist = contact.dist;
-                    for c in contacts.drain(..) {
+                    for (e, c) in contacts.drain(..) {
                         if c.dist < dist {
                             dist = c.dist;
                             contact = c;
+                            other_e = e;
                         }
                     }
                     let n = Vec2::new(contact.normal1[0], contact.normal1[1]);
                     let push_back = n * dist * 1.2;
                     v += push_back;
                     retry = true;
+                    if dist < 0.0 {
+                        collisions.push(WolfCollisionEvent {
+                            a: entity,
+                            b: other_e,
+                            normal: n,
+                            depth: -dist,
+                        });
+                    }
                 }
 
                 new_translation += v;
@@ -700,27 +1011,45 @@ pub fn body_system(
             .grid
             .insert_or_replace(entity, new_translation.truncate());
     }
+
+    if config.dedup_collision_events {
+        dedup_collisions(&mut collisions);
+    }
+    collision_writer.send_batch(collisions);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn door_system(
     mut interact_events: EventReader<WolfInteractEvent>,
-    mut doors: Query<(Entity, &mut WolfDoor, &Children)>,
+    mut doors: Query<(Entity, &mut WolfDoor)>,
     time: Res<Time>,
-    mut transforms: Query<(&mut Transform)>,
+    transforms: Query<&Transform>,
     mut bodies: Query<&mut WolfBody>,
+    inventories: Query<&WolfInventory>,
     world: Res<WolfWorld>,
+    mut denied_writer: EventWriter<WolfDoorDeniedEvent>,
 ) {
     let dt_secs = time.delta_seconds();
-    let door_timer = 0.5;
-    let auto_close_timer = 3.0;
     for ev in interact_events.read() {
-        let Ok((_, mut door, _)) = doors.get_mut(ev.entity) else {
+        let Ok((_, mut door)) = doors.get_mut(ev.entity) else {
             continue;
         };
+        let open_time = door.open_time;
         match &mut door.state {
             DoorState::Closed => {
+                if let Some(lock) = &door.lock {
+                    let has_key = inventories.get(ev.interactor).is_ok_and(|inventory| inventory.has_key(lock));
+                    if !has_key {
+                        denied_writer.send(WolfDoorDeniedEvent {
+                            door: ev.entity,
+                            interactor: ev.interactor,
+                            lock: lock.clone(),
+                        });
+                        continue;
+                    }
+                }
                 door.state = DoorState::Opening {
-                    opening: Timer::start(door_timer),
+                    opening: Timer::start(open_time),
                 };
             }
             DoorState::Closing { closing: _ } => {}
@@ -731,7 +1060,7 @@ fn door_system(
         }
     }
 
-    for (e, mut door, children) in doors.iter_mut() {
+    for (e, mut door) in doors.iter_mut() {
         let Ok(t) = transforms.get(e) else {
             continue;
         };
@@ -739,6 +1068,7 @@ fn door_system(
             continue;
         };
         let p = t.translation.clone();
+        let (open_time, auto_close) = (door.open_time, door.auto_close);
         match &mut door.state {
             crate::DoorState::Closed => {
                 door_body.disabled = false;
@@ -755,60 +1085,201 @@ fn door_system(
                 opening.tick(dt_secs);
                 if opening.is_done() {
                     door.state = DoorState::Open {
-                        auto_close_timer: Timer::start(auto_close_timer),
+                        auto_close_timer: Timer::start(auto_close.unwrap_or_default()),
                     };
                 }
             }
             crate::DoorState::Open { auto_close_timer } => {
                 door_body.disabled = true;
-                let mut blocked = false;
-                for (other_e, other_p) in world.grid.query(p.truncate(), 1.0) {
-                    if other_e != e {
-                        if let Ok(_) = bodies.get(other_e) {
-                            let a = 0.49;
-                            let ab1 = parry2d::bounding_volume::Aabb::from_half_extents(
-                                [p.x, p.y].into(),
-                                [a, a].into(),
-                            );
-                            let ab2 = parry2d::bounding_volume::Aabb::from_half_extents(
-                                [other_p.x, other_p.y].into(),
-                                [a, a].into(),
-                            );
-                            if ab1.intersects(&ab2) {
-                                blocked = true;
-                                break;
-                            }
-                        }
-                    }
-                }
-                if !blocked {
+                // an open door does not close on a body standing in it
+                let filter = WolfQueryFilter::new(door_body.groups).excluding(e);
+                let blocked = !world.overlap(&door_body.shape, p.truncate(), filter).is_empty();
+                if !blocked && auto_close.is_some() {
                     auto_close_timer.tick(dt_secs);
                     if auto_close_timer.is_done() {
                         door.state = DoorState::Closing {
-                            closing: Timer::start(door_timer),
+                            closing: Timer::start(open_time),
                         };
                     }
                 }
             }
         }
+    }
+}
+
+/// Keeps the deepest collision of every pair of entities, as the same pair may have collided
+/// several times in a tick, and from either side.
+fn dedup_collisions(collisions: &mut Vec<WolfCollisionEvent>) {
+    let mut deepest: HashMap<(Entity, Entity), usize> = HashMap::new();
+    let mut kept: Vec<WolfCollisionEvent> = Vec::with_capacity(collisions.len());
+    for collision in collisions.drain(..) {
+        let key = (collision.a.min(collision.b), collision.a.max(collision.b));
+        match deepest.get(&key) {
+            Some(&i) => {
+                if collision.depth > kept[i].depth {
+                    kept[i] = collision;
+                }
+            }
+            None => {
+                deepest.insert(key, kept.len());
+                kept.push(collision);
+            }
+        }
+    }
+    *collisions = kept;
+}
+
+/// Accelerates the controlled entities towards the velocity they wish for and moves them,
+/// sliding along what their bodies hit.
+fn controller_system(
+    mut controllers: Query<(Entity, &mut WolfController, &mut WolfVelocity, &mut Transform, Option<&WolfBody>)>,
+    mut world: ResMut<WolfWorld>,
+    time: Res<Time>,
+    config: Res<WolfConfig>,
+    mut collision_writer: EventWriter<WolfCollisionEvent>,
+) {
+    let dt = time.delta_seconds();
+    let mut collisions: Vec<WolfCollisionEvent> = Vec::new();
+    for (e, mut controller, mut velocity, mut transform, body) in controllers.iter_mut() {
+        let v = velocity.linear;
+        velocity.linear = if controller.wish == Vec2::ZERO {
+            v.clamp_length_max((v.length() - controller.friction * dt).max(0.0))
+        } else {
+            let target = controller.wish * controller.speed;
+            v + (target - v).clamp_length_max(controller.acceleration * dt)
+        };
+        let motion = velocity.linear * dt;
+        if motion == Vec2::ZERO {
+            controller.remaining = Vec2::ZERO;
+            continue;
+        }
+
+        let from = transform.translation.truncate();
+        let result = match body {
+            Some(body) if !body.disabled => {
+                let filter = WolfQueryFilter::new(body.groups).excluding(e);
+                world.move_and_slide(&body.shape, from, motion, filter)
+            }
+            _ => WolfMove {
+                position: from + motion,
+                remaining: Vec2::ZERO,
+                hits: Vec::new(),
+            },
+        };
+        for hit in result.hits.iter() {
+            // stop moving into what was hit, keeping the velocity along it
+            let into = velocity.linear.dot(-hit.normal);
+            if into > 0.0 {
+                velocity.linear += hit.normal * into;
+            }
+            collisions.push(WolfCollisionEvent {
+                a: e,
+                b: hit.entity,
+                normal: -hit.normal,
+                depth: hit.depth,
+            });
+        }
+        controller.remaining = result.remaining;
+        transform.translation = result.position.extend(transform.translation.z);
+        world.grid.insert_or_replace(e, result.position);
+    }
+
+    if config.dedup_collision_events {
+        dedup_collisions(&mut collisions);
+    }
+    collision_writer.send_batch(collisions);
+}
+
+/// Gives the keys to the entities with an inventory entering them.
+fn key_system(
+    mut commands: Commands,
+    mut enter_events: EventReader<WolfTriggerEnter>,
+    keys: Query<&WolfKey>,
+    mut inventories: Query<&mut WolfInventory>,
+    mut pickup_writer: EventWriter<WolfKeyPickupEvent>,
+) {
+    let mut picked_up = Vec::new();
+    for ev in enter_events.read() {
+        if picked_up.contains(&ev.trigger) {
+            continue;
+        }
+        let (Ok(key), Ok(mut inventory)) = (keys.get(ev.trigger), inventories.get_mut(ev.entity)) else {
+            continue;
+        };
+        inventory.add_key(&key.key);
+        picked_up.push(ev.trigger);
+        commands.entity(ev.trigger).despawn_recursive();
+        pickup_writer.send(WolfKeyPickupEvent {
+            entity: ev.entity,
+            key: key.key.clone(),
+        });
+    }
+}
+
+fn trigger_system(
+    mut triggers: Query<(Entity, &Transform, &mut WolfTrigger)>,
+    bodies: Query<&WolfBody>,
+    world: Res<WolfWorld>,
+    mut enter_writer: EventWriter<WolfTriggerEnter>,
+    mut exit_writer: EventWriter<WolfTriggerExit>,
+) {
+    for (trigger_entity, transform, mut trigger) in triggers.iter_mut() {
+        let p = transform.translation.truncate();
+        // bodies are found by their position, so the query reaches beyond the trigger by the size of a large body
+        let radius = trigger.shape.as_shape().compute_local_bounding_sphere().radius() + BODY_REACH;
+        let mut inside = Vec::new();
+        for (e, other_p) in world.grid.query_around(p, radius) {
+            if e == trigger_entity {
+                continue;
+            }
+            let Ok(body) = bodies.get(e) else {
+                continue;
+            };
+            if body.disabled || body.groups.memberships & trigger.filter == 0 {
+                continue;
+            }
+            // bodies merely touching the trigger, like the walls around a tile, are not inside
+            if let Ok(Some(c)) = parry2d::query::contact(
+                &[p.x, p.y].into(),
+                trigger.shape.as_shape(),
+                &[other_p.x, other_p.y].into(),
+                body.shape.as_shape(),
+                0.0,
+            ) {
+                if c.dist < 0.0 {
+                    inside.push(e);
+                }
+            }
+        }
+
+        for e in inside.iter().filter(|e| !trigger.inside.contains(e)) {
+            enter_writer.send(WolfTriggerEnter {
+                trigger: trigger_entity,
+                entity: *e,
+            });
+        }
+        for e in trigger.inside.iter().filter(|e| !inside.contains(e)) {
+            exit_writer.send(WolfTriggerExit {
+                trigger: trigger_entity,
+                entity: *e,
+            });
+        }
+        trigger.inside = inside;
+    }
+}
+
+/// Slides the door meshes according to the state of their doors.
+fn door_render_system(
+    doors: Query<(&WolfDoor, &Children)>,
+    mut transforms: Query<&mut Transform>,
+    time: Res<Time<Fixed>>,
+) {
+    let alpha = time.overstep_fraction();
+    for (door, children) in doors.iter() {
+        let slide = door.prev_slide + (door.slide() - door.prev_slide) * alpha;
         for e in children.iter() {
             if let Ok(mut transform) = transforms.get_mut(*e) {
-                match &door.state {
-                    crate::DoorState::Closed => {
-                        transform.translation.x = 0.0;
-                    }
-                    crate::DoorState::Closing { closing } => {
-                        transform.translation.x = 1.0 - closing.alpha();
-                    }
-                    crate::DoorState::Opening { opening } => {
-                        transform.translation.x = opening.alpha()
-                    }
-                    crate::DoorState::Open {
-                        auto_close_timer: _,
-                    } => {
-                        transform.translation.x = 1.0;
-                    }
-                }
+                transform.translation.x = slide;
             }
         }
     }
@@ -833,15 +1304,21 @@ pub fn push_system(
         if v.x == 0 || v.y == 0 {
             let v = v.as_vec2();
             if v.length() > 0.0 {
-                push.vel = v.extend(0.0);
+                push.vel = (v.normalize() * push.speed).extend(0.0);
             }
         }
     }
 
-    for (e, push) in pushes.iter() {
+    for (e, mut push) in pushes.iter_mut() {
         if push.vel.length() > 0.0 {
             let Ok(mut t) = transforms.get_mut( e) else { continue;};
-            t.translation += push.vel * time.delta_seconds();
+            let mut step = push.vel * time.delta_seconds();
+            // stops once it has moved far enough, after which the post push system removes it
+            if let Some(distance) = push.distance {
+                step = step.clamp_length_max((distance - push.moved).max(0.0));
+            }
+            push.moved += step.length();
+            t.translation += step;
         }
     }
 }
@@ -849,36 +1326,129 @@ pub fn push_system(
 pub fn post_push_system(pushes: Query<(Entity, &mut WolfPush, &Prev<Transform>, &Transform)>, mut commands: Commands) {
     for (e, push, prev_transform, transform) in pushes.iter() {
         if push.vel.length() > 0.0 {
+            // blocked walls are pushed back out of what they ran into, so look for no progress
+            // rather than no movement at all
             let change = transform.translation - prev_transform.translation;
-            if change.length() <= 0.0 {
+            if change.dot(push.vel) <= 0.0 {
                 commands.entity(e).remove::<WolfPush>();
             }
         }
     }
 }
 
-pub fn build_systems(app: &mut App) {
-    app.add_systems(Startup, startup_system);
-    app.add_systems(PreUpdate, (load_map_system).chain());
+pub fn build_simulation_systems(app: &mut App) {
+    app.add_systems(PreUpdate, (tick_rate_system, input_system, map_load_failed_system, load_map_system, hot_reload_map_system).chain());
     app.add_systems(
-        Update,
+        FixedUpdate,
         (
             spawn_system,
             prev_system,
             spatial_hash_system,
-            camera_system,
+            player_system,
             interactor_system,
             push_system,
+            controller_system,
             body_system,
             post_push_system,
             door_system,
+            trigger_system,
+            key_system,
+            camera_follow_system,
+            consume_input_system,
+        )
+            .chain()
+            .in_set(WolfSet::Simulation),
+    );
+}
+
+pub fn build_presentation_systems(app: &mut App) {
+    app.add_systems(Startup, startup_system);
+    app.add_systems(
+        Update,
+        (
+            camera_presentation_system,
+            view_effects_system,
+            map_geometry_system,
+            door_render_system,
             sprite_system,
             ui_system,
-            spatial_hash_system,
             instance_manager_spawn_system,
-            instance_manage_render_system,
         )
-            .chain(),
+            .chain()
+            .in_set(WolfSet::Presentation),
     );
     app.add_systems(PostUpdate, debug_gizmos_system);
+    app.add_systems(
+        PostUpdate,
+        (interpolation_system, apply_view_effects_system, instance_manage_render_system)
+            .chain()
+            .after(TransformSystem::TransformPropagate),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{input::InputPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::{parse_text_map, WolfSimulationPlugin};
+
+    #[test]
+    fn interpolate_between_ticks() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin, TransformPlugin, WolfSimulationPlugin));
+        app.add_systems(Update, door_render_system);
+        app.add_systems(PostUpdate, interpolation_system.after(TransformSystem::TransformPropagate));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 60.0)));
+        app.world.resource_mut::<WolfConfig>().tick_rate = 4.0;
+
+        let map = parse_text_map(
+            std::path::Path::new("interpolate.wolfmap"),
+            "[legend]\n# = block body\nD = door body interact\np = push\n\
+             [grid]\n#######\n#D#...#\n#######\n\
+             [grid]\n.......\n..p....\n.......\n",
+        )
+        .unwrap();
+        let handle = app.world.resource_mut::<Assets<WolfMap>>().add(map);
+        app.world.resource_mut::<WolfWorld>().load_map(handle);
+        for _ in 0..30 {
+            app.update();
+        }
+        assert!(app.world.query::<&WolfPush>().iter(&app.world).next().is_some(), "the map was not spawned");
+
+        let (door, _) = app.world.query::<(Entity, &WolfDoor)>().single(&app.world);
+        let slab = app.world.spawn(TransformBundle::default()).id();
+        app.world.entity_mut(door).add_child(slab);
+        let mut door_state = app.world.get_mut::<WolfDoor>(door).unwrap();
+        door_state.open_time = 2.0;
+        door_state.state = DoorState::Opening { opening: Timer::start(2.0) };
+        let (block, mut push) = app.world.query::<(Entity, &mut WolfPush)>().single_mut(&mut app.world);
+        push.vel = Vec3::X * push.speed;
+
+        let (mut pushed, mut slid) = (0, 0);
+        for _ in 0..60 {
+            app.update();
+            if app.world.resource::<Time<Fixed>>().overstep_fraction() < 0.1 {
+                continue;
+            }
+            let prev = app.world.get::<Prev<Transform>>(block).unwrap().translation.x;
+            let current = app.world.get::<Transform>(block).unwrap().translation.x;
+            let rendered = app.world.get::<GlobalTransform>(block).unwrap().translation().x;
+            if current > prev {
+                assert!(rendered > prev && rendered < current, "push wall at {} is not between {} and {}", rendered, prev, current);
+                pushed += 1;
+            }
+
+            let door_state = app.world.get::<WolfDoor>(door).unwrap();
+            let (prev, current) = (door_state.prev_slide, door_state.slide());
+            let rendered = app.world.get::<Transform>(slab).unwrap().translation.x;
+            if current > prev {
+                assert!(rendered > prev && rendered < current, "door at {} is not between {} and {}", rendered, prev, current);
+                slid += 1;
+            }
+        }
+        assert!(pushed > 10 && slid > 10, "only checked {} frames of the push wall and {} of the door", pushed, slid);
+    }
 }
@@ -0,0 +1,318 @@
+use array2d::Array2D;
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+    utils::HashMap,
+};
+use tiled::PropertyValue;
+
+use crate::{assets::expected_property_type, assets::load_entity, WolfMap, WolfMapLoadError, WolfObjectShape};
+
+/// Loads maps written in the plain-text `.wolfmap` format.
+///
+/// A `[legend]` section maps characters onto classes and properties, one character per line,
+/// followed by one or more `[grid]` sections, each becoming a layer of the map.
+/// Rows shorter than the widest row are filled up with empty tiles.
+/// The first line of a grid is the northernmost row. Spaces and `.` are empty tiles,
+/// and lines starting with `//` in the legend are comments.
+/// An optional `[properties]` section holds the properties of the map itself, one per line.
+///
+/// ```text
+/// [properties]
+/// move_speed = 8.0
+///
+/// [legend]
+/// # = block body; image = images/wall_blue.png
+/// D = door body interact; image = images/door_blue.png
+/// P = player; facing = 90.0
+///
+/// [grid]
+/// #####
+/// #P D#
+/// #####
+/// ```
+///
+/// Property values are typed by the property they are given to if it is known, e.g. `facing`
+/// is always a float, otherwise they are read as a bool, an int, a float or else a string.
+#[derive(Default)]
+struct WolfTextMapAssetLoader;
+
+enum Section {
+    None,
+    Properties,
+    Legend,
+    Grid,
+}
+
+fn invalid(line: usize, reason: String) -> WolfMapLoadError {
+    WolfMapLoadError::InvalidText { line, reason }
+}
+
+/// Reads the value of a property, typed by the property if it is known.
+pub(crate) fn parse_value(property: &str, value: &str) -> Option<PropertyValue> {
+    // the first of the alternative types the value can be read as is used
+    match expected_property_type(property) {
+        Some(expected) => expected.split('|').find_map(|t| match t {
+            "float" => value.parse().ok().map(PropertyValue::FloatValue),
+            "int" => value.parse().ok().map(PropertyValue::IntValue),
+            "bool" => value.parse().ok().map(PropertyValue::BoolValue),
+            _ => Some(PropertyValue::StringValue(value.to_string())),
+        }),
+        None => Some(if let Ok(v) = value.parse() {
+            PropertyValue::BoolValue(v)
+        } else if let Ok(v) = value.parse() {
+            PropertyValue::IntValue(v)
+        } else if let Ok(v) = value.parse() {
+            PropertyValue::FloatValue(v)
+        } else {
+            PropertyValue::StringValue(value.to_string())
+        }),
+    }
+}
+
+/// Parses a `property = value` pair into the properties.
+fn parse_property(line_number: usize, part: &str, properties: &mut tiled::Properties) -> Result<(), WolfMapLoadError> {
+    let Some((property, value)) = part.split_once('=') else {
+        return Err(invalid(line_number, format!("expected property=value, found '{}'", part)));
+    };
+    let (property, value) = (property.trim(), value.trim());
+    let Some(value) = parse_value(property, value) else {
+        return Err(invalid(line_number, format!("invalid value '{}' of property '{}'", value, property)));
+    };
+    properties.insert(property.to_string(), value);
+    Ok(())
+}
+
+/// Parses a `.wolfmap` file, where `path` is used to resolve file properties.
+pub fn parse_text_map(path: &std::path::Path, text: &str) -> Result<WolfMap, WolfMapLoadError> {
+    let mut legend: HashMap<char, (String, tiled::Properties)> = HashMap::new();
+    let mut grids: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut map_properties = tiled::Properties::new();
+    let mut section = Section::None;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        match line.trim() {
+            "[properties]" => {
+                section = Section::Properties;
+                continue;
+            }
+            "[legend]" => {
+                section = Section::Legend;
+                continue;
+            }
+            "[grid]" => {
+                section = Section::Grid;
+                grids.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        match section {
+            Section::None => {
+                if !line.trim().is_empty() {
+                    return Err(invalid(line_number, "expected [properties], [legend] or [grid]".to_string()));
+                }
+            }
+            Section::Properties => {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with("//") {
+                    continue;
+                }
+                parse_property(line_number, line, &mut map_properties)?;
+            }
+            Section::Legend => {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with("//") {
+                    continue;
+                }
+                let mut chars = line.chars();
+                let Some(c) = chars.next() else {
+                    continue;
+                };
+                let Some(definition) = chars.as_str().trim_start().strip_prefix('=') else {
+                    return Err(invalid(line_number, "expected '=' after the legend character".to_string()));
+                };
+                let mut parts = definition.split(';');
+                let class = parts.next().unwrap_or_default().trim().to_string();
+                let mut properties = tiled::Properties::new();
+                for part in parts {
+                    let part = part.trim();
+                    if part.is_empty() {
+                        continue;
+                    }
+                    parse_property(line_number, part, &mut properties)?;
+                }
+                legend.insert(c, (class, properties));
+            }
+            Section::Grid => {
+                if let Some(grid) = grids.last_mut() {
+                    grid.push((line_number, line.trim_end()));
+                }
+            }
+        }
+    }
+
+    if grids.is_empty() {
+        return Err(invalid(text.lines().count().max(1), "expected a [grid] section".to_string()));
+    }
+
+    // trailing empty lines are not part of the grid
+    for grid in grids.iter_mut() {
+        while grid.last().map(|(_, line)| line.is_empty()) == Some(true) {
+            grid.pop();
+        }
+    }
+    let width = grids
+        .iter()
+        .flat_map(|grid| grid.iter().map(|(_, line)| line.chars().count()))
+        .max()
+        .unwrap_or(0) as u32;
+    let height = grids.iter().map(|grid| grid.len()).max().unwrap_or(0) as u32;
+
+    let mut layers = Vec::new();
+    for grid in grids.iter() {
+        let mut layer = Array2D::filled_with(None, height as usize, width as usize);
+        for (row, (line_number, line)) in grid.iter().enumerate() {
+            let y = height - row as u32 - 1;
+            for (x, c) in line.chars().enumerate() {
+                if c == ' ' || c == '.' {
+                    continue;
+                }
+                let Some((class, properties)) = legend.get(&c) else {
+                    return Err(invalid(*line_number, format!("'{}' is not in the legend", c)));
+                };
+                let wolf_entity = load_entity(
+                    path,
+                    Some(class),
+                    &[properties],
+                    Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.5),
+                    0.0,
+                    WolfObjectShape::Tile,
+                )?;
+                *layer.get_mut(y as usize, x).unwrap() = Some(wolf_entity);
+            }
+        }
+        layers.push(layer);
+    }
+
+    let properties = load_entity(path, None, &[&map_properties], Vec3::ZERO, 0.0, WolfObjectShape::Tile)?;
+    Ok(WolfMap {
+        layers,
+        objects: Vec::new(),
+        width,
+        height,
+        properties,
+    })
+}
+
+impl AssetLoader for WolfTextMapAssetLoader {
+    type Asset = WolfMap;
+
+    type Settings = ();
+
+    type Error = WolfMapLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+            parse_text_map(load_context.path(), &text)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wolfmap"]
+    }
+}
+
+pub fn build_text_map(app: &mut App) {
+    app.init_asset_loader::<WolfTextMapAssetLoader>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<WolfMap, WolfMapLoadError> {
+        parse_text_map(std::path::Path::new("maps/test.wolfmap"), text)
+    }
+
+    fn tile(map: &WolfMap, x: usize, row: usize) -> Option<&crate::WolfEntity> {
+        map.layers[0][(map.height as usize - row - 1, x)].as_ref()
+    }
+
+    #[test]
+    fn legend_properties() {
+        let map = parse(
+            "[legend]\n\
+             // the player\n\
+             P = player body; facing = 90; body_shape = ball; body_radius = 0.4; tag = start; hidden = true; score = 3\n\
+             [grid]\n\
+             P\n",
+        )
+        .unwrap();
+        let player = tile(&map, 0, 0).unwrap();
+        assert!(player.has_class("player") && player.has_class("body"));
+        assert_eq!(player.start_facing(), 90.0);
+        assert_eq!(player.get_property_f32("body_radius"), Some(&0.4));
+        assert_eq!(player.get_property_string("body_shape").unwrap(), "ball");
+        assert_eq!(player.get_property_string("tag").unwrap(), "start");
+        assert_eq!(player.get_property_bool("hidden"), Some(&true));
+        assert_eq!(player.get_property_int("score"), Some(&3));
+    }
+
+    #[test]
+    fn empty_cells() {
+        let map = parse("[legend]\n# = block body\n[grid]\n#.#\n# #\n").unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert!(tile(&map, 0, 0).is_some_and(|we| we.has_class("block")));
+        assert!(tile(&map, 1, 0).is_none());
+        assert!(tile(&map, 1, 1).is_none());
+        assert_eq!(tile(&map, 2, 1).unwrap().start_pos(), Vec3::new(2.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn ragged_grid() {
+        let map = parse("[legend]\n# = block\n[grid]\n#\n###\n##\n\n").unwrap();
+        assert_eq!((map.width, map.height), (3, 3));
+        assert!(tile(&map, 0, 0).is_some());
+        assert!(tile(&map, 1, 0).is_none());
+        assert!(tile(&map, 2, 0).is_none());
+        assert!(tile(&map, 2, 1).is_some());
+        assert!(tile(&map, 2, 2).is_none());
+    }
+
+    #[test]
+    fn unknown_legend_char() {
+        let err = parse("[legend]\n# = block\n[grid]\n###\n#X#\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 5, .. }), "{}", err);
+    }
+
+    #[test]
+    fn missing_grid() {
+        let err = parse("[legend]\n# = block\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 2, .. }), "{}", err);
+        assert!(matches!(parse(""), Err(WolfMapLoadError::InvalidText { .. })));
+    }
+
+    #[test]
+    fn bad_properties() {
+        let err = parse("[legend]\nP = player; facing\n[grid]\nP\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 2, .. }), "{}", err);
+        let err = parse("[legend]\n# = block\nP = player; facing = north\n[grid]\nP\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 3, .. }), "{}", err);
+        let err = parse("[legend]\nP player\n[grid]\nP\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 2, .. }), "{}", err);
+        let err = parse("[properties]\nmove_speed = fast\n[grid]\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 2, .. }), "{}", err);
+        let err = parse("[legend]\nP = player; component:Health = 3\n[grid]\nP\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidPropertyType { expected: "string", found: "int", .. }), "{}", err);
+        let err = parse("text before a section\n[grid]\n").unwrap_err();
+        assert!(matches!(err, WolfMapLoadError::InvalidText { line: 1, .. }), "{}", err);
+    }
+}
@@ -0,0 +1,529 @@
+use array2d::Array2D;
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tiled::PropertyValue;
+
+use crate::{assets::load_entity, text_map::parse_value, WolfEntity, WolfMap, WolfMapLoadError, WolfObjectShape};
+
+/// Maps a range of tile numbers of a plane onto a class.
+///
+/// A `{}` in the image is replaced by the tile number.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Wolf3dTiles {
+    pub plane: usize,
+    pub from: u16,
+    pub to: u16,
+    pub class: String,
+    pub image: Option<String>,
+    pub facing: Option<f32>,
+    /// Properties given to the entities, e.g. the `lock` of a door, typed like the properties
+    /// of a `.wolfmap`.
+    #[serde(default)]
+    pub properties: Vec<(String, String)>,
+}
+
+impl Wolf3dTiles {
+    pub fn new(plane: usize, from: u16, to: u16, class: &str) -> Self {
+        Self {
+            plane,
+            from,
+            to,
+            class: class.to_string(),
+            image: None,
+            facing: None,
+            properties: Vec::new(),
+        }
+    }
+
+    pub fn with_image(mut self, image: &str) -> Self {
+        self.image = Some(image.to_string());
+        self
+    }
+
+    pub fn with_facing(mut self, facing: f32) -> Self {
+        self.facing = Some(facing);
+        self
+    }
+
+    pub fn with_property(mut self, property: &str, value: &str) -> Self {
+        self.properties.push((property.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// The table used to turn the tile numbers of the walls plane (0) and the objects plane (1)
+/// into classes, where the first matching entry is used and unmatched tiles are left empty.
+///
+/// The gold and silver doors are locked, opened by the gold and silver keys.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Wolf3dMapSettings {
+    pub tiles: Vec<Wolf3dTiles>,
+}
+
+impl Default for Wolf3dMapSettings {
+    fn default() -> Self {
+        // players are balls, like the player start of a Tiled map
+        let player = |tile: u16, facing: f32| {
+            Wolf3dTiles::new(1, tile, tile, "player body")
+                .with_facing(facing)
+                .with_property("body_shape", "ball")
+                .with_property("body_radius", "0.4")
+        };
+        Self {
+            tiles: vec![
+                Wolf3dTiles::new(0, 1, 63, "block body").with_image("images/wolf3d/wall_{}.png"),
+                Wolf3dTiles::new(0, 92, 93, "door body interact")
+                    .with_image("images/wolf3d/door_{}.png")
+                    .with_property("lock", "gold"),
+                Wolf3dTiles::new(0, 94, 95, "door body interact")
+                    .with_image("images/wolf3d/door_{}.png")
+                    .with_property("lock", "silver"),
+                Wolf3dTiles::new(0, 90, 101, "door body interact").with_image("images/wolf3d/door_{}.png"),
+                player(19, 90.0),
+                player(20, 0.0),
+                player(21, 270.0),
+                player(22, 180.0),
+                Wolf3dTiles::new(1, 43, 43, "key sprite")
+                    .with_image("images/wolf3d/static_{}.png")
+                    .with_property("key", "gold"),
+                Wolf3dTiles::new(1, 44, 44, "key sprite")
+                    .with_image("images/wolf3d/static_{}.png")
+                    .with_property("key", "silver"),
+                Wolf3dTiles::new(1, 23, 74, "sprite").with_image("images/wolf3d/static_{}.png"),
+                Wolf3dTiles::new(1, 98, 98, "push"),
+                Wolf3dTiles::new(1, 108, 255, "sprite body").with_image("images/wolf3d/actor_{}.png"),
+            ],
+        }
+    }
+}
+
+const MAP_COUNT: usize = 100;
+const PLANE_COUNT: usize = 3;
+const CARMACK_NEAR: u8 = 0xA7;
+const CARMACK_FAR: u8 = 0xA8;
+
+fn invalid(reason: &str) -> WolfMapLoadError {
+    WolfMapLoadError::InvalidWolf3d(reason.to_string())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, WolfMapLoadError> {
+    match bytes.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(invalid("unexpected end of data")),
+    }
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Result<i32, WolfMapLoadError> {
+    match bytes.get(offset..offset + 4) {
+        Some(b) => Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(invalid("unexpected end of data")),
+    }
+}
+
+/// Expands Carmack compressed data into words, where the first word of the data is the
+/// expanded length in bytes.
+pub fn carmack_expand(bytes: &[u8]) -> Result<Vec<u16>, WolfMapLoadError> {
+    let length = read_u16(bytes, 0)? as usize / 2;
+    let mut words: Vec<u16> = Vec::with_capacity(length);
+    let mut i = 2;
+    while words.len() < length {
+        let word = read_u16(bytes, i)?;
+        i += 2;
+        let [count, tag] = word.to_le_bytes();
+        if tag != CARMACK_NEAR && tag != CARMACK_FAR {
+            words.push(word);
+            continue;
+        }
+        if count == 0 {
+            // an escaped word having the tag as its high byte
+            let low = *bytes.get(i).ok_or_else(|| invalid("unexpected end of data"))?;
+            i += 1;
+            words.push(u16::from_le_bytes([low, tag]));
+            continue;
+        }
+        let start = if tag == CARMACK_NEAR {
+            let back = *bytes.get(i).ok_or_else(|| invalid("unexpected end of data"))? as usize;
+            i += 1;
+            words.len().checked_sub(back).ok_or_else(|| invalid("carmack pointer out of range"))?
+        } else {
+            let offset = read_u16(bytes, i)? as usize;
+            i += 2;
+            offset
+        };
+        for j in start..start + count as usize {
+            let word = *words.get(j).ok_or_else(|| invalid("carmack pointer out of range"))?;
+            words.push(word);
+        }
+    }
+    words.truncate(length);
+    Ok(words)
+}
+
+/// Expands RLEW compressed words, where the first word is the expanded length in bytes.
+pub fn rlew_expand(words: &[u16], tag: u16) -> Result<Vec<u16>, WolfMapLoadError> {
+    let length = *words.first().ok_or_else(|| invalid("unexpected end of data"))? as usize / 2;
+    let mut expanded = Vec::with_capacity(length);
+    let mut iter = words[1..].iter();
+    while expanded.len() < length {
+        let word = *iter.next().ok_or_else(|| invalid("unexpected end of data"))?;
+        if word == tag {
+            let count = *iter.next().ok_or_else(|| invalid("unexpected end of data"))?;
+            let value = *iter.next().ok_or_else(|| invalid("unexpected end of data"))?;
+            for _ in 0..count {
+                expanded.push(value);
+            }
+        } else {
+            expanded.push(word);
+        }
+    }
+    expanded.truncate(length);
+    Ok(expanded)
+}
+
+/// Reads the map whose header is at `offset` in the GAMEMAPS data.
+fn read_map(
+    path: &Path,
+    settings: &Wolf3dMapSettings,
+    gamemaps: &[u8],
+    offset: usize,
+    rlew_tag: u16,
+) -> Result<WolfMap, WolfMapLoadError> {
+    let mut plane_starts = [0; PLANE_COUNT];
+    let mut plane_lengths = [0; PLANE_COUNT];
+    for plane in 0..PLANE_COUNT {
+        plane_starts[plane] = read_i32(gamemaps, offset + plane * 4)? as usize;
+        plane_lengths[plane] = read_u16(gamemaps, offset + 12 + plane * 2)? as usize;
+    }
+    let width = read_u16(gamemaps, offset + 18)? as u32;
+    let height = read_u16(gamemaps, offset + 20)? as u32;
+
+    let mut layers = Vec::new();
+    for plane in 0..2 {
+        let start = plane_starts[plane];
+        let end = start.checked_add(plane_lengths[plane]).ok_or_else(|| invalid("plane out of range"))?;
+        let Some(compressed) = gamemaps.get(start..end) else {
+            return Err(invalid("plane out of range"));
+        };
+        let tiles = rlew_expand(&carmack_expand(compressed)?, rlew_tag)?;
+        if tiles.len() < width as usize * height as usize {
+            return Err(invalid("plane smaller than map"));
+        }
+
+        let mut layer = Array2D::filled_with(None, height as usize, width as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let tile = tiles[y as usize * width as usize + x as usize];
+                let Some(entry) = settings
+                    .tiles
+                    .iter()
+                    .find(|t| t.plane == plane && t.from <= tile && tile <= t.to)
+                else {
+                    continue;
+                };
+                let y = height - y - 1;
+                let mut properties = tiled::Properties::new();
+                if let Some(image) = &entry.image {
+                    properties.insert("image".to_string(), PropertyValue::StringValue(image.replace("{}", &tile.to_string())));
+                }
+                for (property, value) in entry.properties.iter() {
+                    let Some(value) = parse_value(property, value) else {
+                        return Err(invalid(&format!("invalid value '{}' of property '{}'", value, property)));
+                    };
+                    properties.insert(property.clone(), value);
+                }
+                properties.insert("wolf3d_tile".to_string(), PropertyValue::IntValue(tile as i32));
+                let wolf_entity = load_entity(
+                    path,
+                    Some(&entry.class),
+                    &[&properties],
+                    Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.5),
+                    entry.facing.unwrap_or(0.0),
+                    WolfObjectShape::Tile,
+                )?;
+                *layer.get_mut(y as usize, x as usize).unwrap() = Some(wolf_entity);
+            }
+        }
+        layers.push(layer);
+    }
+
+    Ok(WolfMap {
+        layers,
+        objects: Vec::new(),
+        width,
+        height,
+        properties: WolfEntity::default(),
+    })
+}
+
+/// Reads the maps of MAPHEAD and GAMEMAPS data together with their index, leaving out the
+/// maps which are not present.
+pub fn parse_wolf3d_maps(
+    path: &Path,
+    settings: &Wolf3dMapSettings,
+    maphead: &[u8],
+    gamemaps: &[u8],
+) -> Result<Vec<(usize, WolfMap)>, WolfMapLoadError> {
+    let rlew_tag = read_u16(maphead, 0)?;
+    let mut maps = Vec::new();
+    for index in 0..MAP_COUNT {
+        let Ok(offset) = read_i32(maphead, 2 + index * 4) else {
+            break;
+        };
+        // maps not present have an offset of either 0 or -1
+        if offset <= 0 {
+            continue;
+        }
+        maps.push((index, read_map(path, settings, gamemaps, offset as usize, rlew_tag)?));
+    }
+    Ok(maps)
+}
+
+/// Loads the maps of the original Wolfenstein 3D data from a MAPHEAD file and the GAMEMAPS
+/// file next to it, e.g. `MAPHEAD.WL6` and `GAMEMAPS.WL6`.
+///
+/// The first map is the loaded asset, while every map is available as a labeled asset,
+/// e.g. `MAPHEAD.WL6#Map9`.
+#[derive(Default)]
+struct Wolf3dMapAssetLoader;
+
+impl AssetLoader for Wolf3dMapAssetLoader {
+    type Asset = WolfMap;
+
+    type Settings = Wolf3dMapSettings;
+
+    type Error = WolfMapLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut maphead = Vec::new();
+            reader.read_to_end(&mut maphead).await?;
+
+            let file_name = load_context
+                .path()
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let gamemaps_name = if let Some(rest) = file_name.strip_prefix("MAPHEAD") {
+                format!("GAMEMAPS{}", rest)
+            } else if let Some(rest) = file_name.strip_prefix("maphead") {
+                format!("gamemaps{}", rest)
+            } else {
+                return Err(invalid("file name must start with MAPHEAD"));
+            };
+            let gamemaps_path = load_context.path().with_file_name(gamemaps_name);
+            let gamemaps = load_context.read_asset_bytes(gamemaps_path).await?;
+
+            let maps = parse_wolf3d_maps(load_context.path(), settings, &maphead, &gamemaps)?;
+            let Some((_, first)) = maps.first().cloned() else {
+                return Err(invalid("no maps found"));
+            };
+            for (index, map) in maps {
+                load_context.add_labeled_asset(format!("Map{}", index), map);
+            }
+            Ok(first)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wl1", "wl3", "wl6", "sdm", "sod"]
+    }
+}
+
+pub fn build_wolf3d(app: &mut App) {
+    app.init_asset_loader::<Wolf3dMapAssetLoader>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WolfShape;
+
+    const TAG: u16 = 0xABCD;
+
+    fn bytes(words: &[u16]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn carmack_near() {
+        let mut data = bytes(&[8, 1, 2, u16::from_le_bytes([2, CARMACK_NEAR])]);
+        data.push(2);
+        assert_eq!(carmack_expand(&data).unwrap(), vec![1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn carmack_far() {
+        let data = bytes(&[10, 1, 2, 3, u16::from_le_bytes([2, CARMACK_FAR]), 1]);
+        assert_eq!(carmack_expand(&data).unwrap(), vec![1, 2, 3, 2, 3]);
+    }
+
+    #[test]
+    fn carmack_escape() {
+        let mut data = bytes(&[4, u16::from_le_bytes([0, CARMACK_NEAR])]);
+        data.push(0x05);
+        data.extend(bytes(&[u16::from_le_bytes([0, CARMACK_FAR])]));
+        data.push(0x12);
+        assert_eq!(carmack_expand(&data).unwrap(), vec![0xA705, 0xA812]);
+    }
+
+    #[test]
+    fn carmack_invalid() {
+        // truncated
+        assert!(carmack_expand(&[]).is_err());
+        assert!(carmack_expand(&bytes(&[8, 1])).is_err());
+        assert!(carmack_expand(&bytes(&[4, u16::from_le_bytes([0, CARMACK_NEAR])])).is_err());
+        assert!(carmack_expand(&bytes(&[4, 1, u16::from_le_bytes([1, CARMACK_FAR])])).is_err());
+        // pointing back further than the start
+        let mut data = bytes(&[4, 1, u16::from_le_bytes([1, CARMACK_NEAR])]);
+        data.push(5);
+        assert!(carmack_expand(&data).is_err());
+        // pointing past what has been expanded
+        assert!(carmack_expand(&bytes(&[6, 1, u16::from_le_bytes([2, CARMACK_FAR]), 3])).is_err());
+    }
+
+    #[test]
+    fn rlew() {
+        assert_eq!(rlew_expand(&[10, 1, TAG, 3, 7, 2], TAG).unwrap(), vec![1, 7, 7, 7, 2]);
+        // a run of 0 is nothing
+        assert_eq!(rlew_expand(&[4, TAG, 0, 7, 1, 2], TAG).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn rlew_invalid() {
+        assert!(rlew_expand(&[], TAG).is_err());
+        assert!(rlew_expand(&[10, 1, TAG, 3], TAG).is_err());
+        assert!(rlew_expand(&[6, 1, 2], TAG).is_err());
+    }
+
+    /// Compresses a plane like the original data, RLEW compressing runs of the same tile
+    /// and then Carmack compressing it without any pointers.
+    fn compress(tiles: &[u16]) -> Vec<u8> {
+        let mut rlew = vec![(tiles.len() * 2) as u16];
+        let mut i = 0;
+        while i < tiles.len() {
+            let run = tiles[i..].iter().take_while(|t| **t == tiles[i]).count();
+            if run > 3 {
+                rlew.extend([TAG, run as u16, tiles[i]]);
+            } else {
+                rlew.extend(&tiles[i..i + run]);
+            }
+            i += run;
+        }
+        let mut carmack = bytes(&[(rlew.len() * 2) as u16]);
+        for word in rlew {
+            let [low, high] = word.to_le_bytes();
+            if high == CARMACK_NEAR || high == CARMACK_FAR {
+                carmack.extend([0, high, low]);
+            } else {
+                carmack.extend(word.to_le_bytes());
+            }
+        }
+        carmack
+    }
+
+    /// A MAPHEAD and GAMEMAPS pair where map 1 is the only map present.
+    fn wolf3d_data(walls: &[u16], objects: &[u16]) -> (Vec<u8>, Vec<u8>) {
+        let mut gamemaps = b"TED5v1.0".to_vec();
+        let mut planes = Vec::new();
+        let empty = vec![0; 64 * 64];
+        for plane in [walls, objects, &empty] {
+            let data = compress(plane);
+            planes.push((gamemaps.len() as i32, data.len() as u16));
+            gamemaps.extend(data);
+        }
+        let header = gamemaps.len() as i32;
+        for (start, _) in planes.iter() {
+            gamemaps.extend(start.to_le_bytes());
+        }
+        for (_, length) in planes.iter() {
+            gamemaps.extend(length.to_le_bytes());
+        }
+        gamemaps.extend(bytes(&[64, 64]));
+        gamemaps.extend(b"Test\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        let mut maphead = TAG.to_le_bytes().to_vec();
+        maphead.extend(0i32.to_le_bytes());
+        maphead.extend(header.to_le_bytes());
+        maphead.extend((-1i32).to_le_bytes());
+        (maphead, gamemaps)
+    }
+
+    #[test]
+    fn maps() {
+        let mut walls = vec![0; 64 * 64];
+        let mut objects = vec![0; 64 * 64];
+        for i in 0..64 {
+            walls[i] = 1;
+            walls[63 * 64 + i] = 1;
+            walls[i * 64] = 1;
+            walls[i * 64 + 63] = 1;
+        }
+        walls[2 * 64 + 5] = 92;
+        walls[2 * 64 + 6] = 0xA7A8;
+        objects[2 * 64 + 2] = 19;
+        objects[2 * 64 + 3] = 43;
+        objects[2 * 64 + 4] = 98;
+        let (maphead, gamemaps) = wolf3d_data(&walls, &objects);
+
+        let maps = parse_wolf3d_maps(Path::new("maps/MAPHEAD.WL6"), &Wolf3dMapSettings::default(), &maphead, &gamemaps).unwrap();
+        assert_eq!(maps.len(), 1);
+        let (index, map) = &maps[0];
+        assert_eq!(*index, 1);
+        assert_eq!((map.width, map.height, map.layers.len()), (64, 64, 2));
+
+        // the first row of the data is the northernmost
+        let tile = |layer: usize, x: usize, y: usize| map.layers[layer][(63 - y, x)].as_ref();
+        let wall = tile(0, 0, 0).unwrap();
+        assert!(wall.has_class("block") && wall.has_class("body"));
+        assert_eq!(wall.get_property_string("image").unwrap(), "images/wolf3d/wall_1.png");
+        assert_eq!(wall.get_property_int("wolf3d_tile"), Some(&1));
+        assert_eq!(wall.start_pos(), Vec3::new(0.5, 63.5, 0.5));
+        assert!(tile(0, 1, 1).is_none());
+        // tiles missing from the table are left empty
+        assert!(tile(0, 6, 2).is_none());
+
+        let door = tile(0, 5, 2).unwrap();
+        assert!(door.has_class("door") && door.has_class("interact"));
+        assert_eq!(door.get_property_string("lock").unwrap(), "gold");
+
+        let player = tile(1, 2, 2).unwrap();
+        assert!(player.has_class("player") && player.has_class("body"));
+        assert_eq!(player.start_facing(), 90.0);
+        assert!(matches!(WolfShape::from_entity(player), Ok(WolfShape::Ball(ball)) if ball.radius == 0.4));
+
+        let key = tile(1, 3, 2).unwrap();
+        assert!(key.has_class("key") && key.has_class("sprite"));
+        assert_eq!(key.get_property_string("key").unwrap(), "gold");
+        assert!(tile(1, 4, 2).unwrap().has_class("push"));
+    }
+
+    #[test]
+    fn maps_invalid() {
+        let (maphead, gamemaps) = wolf3d_data(&[1; 64 * 64], &[0; 64 * 64]);
+        let settings = Wolf3dMapSettings::default();
+        let path = Path::new("maps/MAPHEAD.WL6");
+        assert!(parse_wolf3d_maps(path, &settings, &[], &gamemaps).is_err());
+        assert!(parse_wolf3d_maps(path, &settings, &maphead, &gamemaps[..gamemaps.len() / 2]).is_err());
+        assert!(parse_wolf3d_maps(path, &settings, &maphead, &gamemaps[..gamemaps.len() - 20]).is_err());
+
+        // a map header pointing past the end of the data
+        let mut maphead_past = maphead.clone();
+        maphead_past[6..10].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert!(parse_wolf3d_maps(path, &settings, &maphead_past, &gamemaps).is_err());
+
+        // a plane pointing past the end of the data
+        let header = i32::from_le_bytes(maphead[6..10].try_into().unwrap()) as usize;
+        let mut gamemaps_past = gamemaps.clone();
+        gamemaps_past[header..header + 4].copy_from_slice(&(-2i32).to_le_bytes());
+        assert!(parse_wolf3d_maps(path, &settings, &maphead, &gamemaps_past).is_err());
+    }
+}
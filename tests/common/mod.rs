@@ -0,0 +1,103 @@
+//! Runs the `WolfSimulationPlugin` headless, without a window or a GPU.
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::event::ManualEventReader, input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+use bevy_wolf::*;
+
+/// The simulation ticks run since the map was spawned, including the one spawning it.
+#[derive(Resource, Default)]
+pub struct Ticks(pub u32);
+
+fn count_ticks(mut ticks: ResMut<Ticks>, players: Query<(), With<WolfPlayer>>) {
+    if !players.is_empty() {
+        ticks.0 += 1;
+    }
+}
+
+/// The duration of a simulation tick at the default tick rate.
+pub fn tick() -> Duration {
+    Duration::from_secs_f64(1.0 / WolfConfig::default().tick_rate)
+}
+
+/// An app loading a map from `tests/maps`, where every update advances the time by `frame`.
+///
+/// Returns once the map has been spawned by the first tick.
+pub fn app(map: &str, frame: Duration) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            file_path: "tests/maps".to_string(),
+            ..Default::default()
+        },
+        InputPlugin,
+        WolfSimulationPlugin,
+    ));
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+    app.init_resource::<Ticks>();
+    app.add_systems(FixedUpdate, count_ticks.after(WolfSet::Simulation));
+
+    let handle = app.world.resource::<AssetServer>().load(map.to_string());
+    app.world.resource_mut::<WolfWorld>().load_map(handle);
+    // the map is loaded in the background
+    let start = Instant::now();
+    while app.world.resource::<Ticks>().0 == 0 {
+        assert!(start.elapsed() < Duration::from_secs(10), "map '{}' was not loaded", map);
+        std::thread::sleep(Duration::from_millis(1));
+        app.update();
+    }
+    app
+}
+
+/// Holds exactly the given keys, pressing them if they were not held already.
+pub fn hold(app: &mut App, keys: &[KeyCode]) {
+    let mut input = app.world.resource_mut::<ButtonInput<KeyCode>>();
+    let released: Vec<KeyCode> = input.get_pressed().filter(|key| !keys.contains(key)).copied().collect();
+    for key in released {
+        input.release(key);
+    }
+    for key in keys {
+        input.press(*key);
+    }
+}
+
+/// Runs the updates, returning the collisions sent meanwhile.
+pub fn run(app: &mut App, updates: u32) -> Vec<WolfCollisionEvent> {
+    let mut reader = ManualEventReader::<WolfCollisionEvent>::default();
+    let mut collisions = Vec::new();
+    for _ in 0..updates {
+        app.update();
+        collisions.extend(reader.read(app.world.resource::<Events<WolfCollisionEvent>>()).copied());
+    }
+    collisions
+}
+
+/// Runs the updates, returning the events read meanwhile, as events only last two updates.
+pub fn run_reading<E: Event + Clone>(app: &mut App, reader: &mut ManualEventReader<E>, updates: u32) -> Vec<E> {
+    let mut events = Vec::new();
+    for _ in 0..updates {
+        app.update();
+        events.extend(reader.read(app.world.resource::<Events<E>>()).cloned());
+    }
+    events
+}
+
+pub fn player(app: &mut App) -> (Entity, Vec2) {
+    let (e, transform) = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<WolfPlayer>>()
+        .single(&app.world);
+    (e, transform.translation.truncate())
+}
+
+/// The entity placed in the tile with the class.
+pub fn entity_at(app: &mut App, tile: UVec2, class: &str) -> Entity {
+    app.world
+        .query::<(Entity, &WolfEntity)>()
+        .iter(&app.world)
+        .find(|(_, we)| we.has_class(class) && we.start_pos().truncate().floor().as_uvec2() == tile)
+        .map(|(e, _)| e)
+        .unwrap_or_else(|| panic!("no {} at {}", class, tile))
+}
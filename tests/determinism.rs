@@ -0,0 +1,60 @@
+mod common;
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_wolf::*;
+use common::*;
+
+const TICKS: u32 = 300;
+
+/// The keys held in each tick, walking, turning, running, strafing and opening a door.
+fn script(tick: u32) -> &'static [KeyCode] {
+    match tick {
+        1..=60 => &[KeyCode::ArrowUp],
+        61..=80 => &[KeyCode::ArrowUp, KeyCode::ArrowLeft],
+        81..=120 => &[KeyCode::ArrowRight],
+        121 => &[KeyCode::Space],
+        122..=200 => &[KeyCode::ArrowUp, KeyCode::ShiftLeft],
+        201..=240 => &[KeyCode::AltLeft, KeyCode::ArrowLeft],
+        _ => &[],
+    }
+}
+
+/// Runs the script at a frame rate, returning the transforms of the map entities, where the
+/// player ended up and the number of updates it took.
+fn simulate(frame: Duration) -> (Vec<Transform>, Vec2, u32) {
+    let mut app = app("determinism.wolfmap", frame);
+    app.insert_resource(WolfInputMap::classic());
+    let mut updates = 0;
+    loop {
+        let tick = app.world.resource::<Ticks>().0;
+        if tick >= TICKS {
+            break;
+        }
+        // frames are at most a tick long, so the keys held in a frame are those of its tick
+        hold(&mut app, script(tick));
+        app.update();
+        updates += 1;
+    }
+
+    let (_, player) = player(&mut app);
+    let world = app.world.resource::<WolfWorld>();
+    let entities: Vec<Entity> = world.map.entities().filter_map(|(key, _)| world.map_entity(key)).collect();
+    let transforms = entities
+        .iter()
+        .map(|e| *app.world.get::<Transform>(*e).unwrap())
+        .collect();
+    (transforms, player, updates)
+}
+
+#[test]
+fn same_results_at_any_frame_rate() {
+    let (a, player_a, updates_a) = simulate(tick());
+    let (b, player_b, updates_b) = simulate(tick().mul_f64(0.37));
+    assert_eq!(updates_a, TICKS - 1);
+    assert!(updates_b > updates_a * 2);
+    assert_ne!(player_a, Vec2::new(1.5, 4.5));
+    assert_eq!(player_a, player_b);
+    assert_eq!(a, b);
+}
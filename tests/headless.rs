@@ -0,0 +1,220 @@
+mod common;
+
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+use bevy_wolf::*;
+use common::*;
+
+#[test]
+fn door() {
+    let mut app = app("door.wolfmap", tick());
+    let door = entity_at(&mut app, UVec2::new(3, 1), "door");
+
+    // walk into the closed door
+    hold(&mut app, &[KeyCode::KeyW]);
+    run(&mut app, 60);
+    let (_, pos) = player(&mut app);
+    assert!(pos.x > 2.5 && pos.x <= 2.6, "player at {} is not in front of the door", pos);
+
+    hold(&mut app, &[KeyCode::Space]);
+    run(&mut app, 1);
+    hold(&mut app, &[]);
+    run(&mut app, 40);
+    let state = &app.world.get::<WolfDoor>(door).unwrap().state;
+    assert!(matches!(state, DoorState::Open { .. }));
+    assert!(app.world.get::<WolfBody>(door).unwrap().disabled);
+
+    // walk through the open door
+    hold(&mut app, &[KeyCode::KeyW]);
+    run(&mut app, 60);
+    let (_, pos) = player(&mut app);
+    assert!(pos.x > 4.5 && pos.x <= 5.6, "player at {} did not walk through the door", pos);
+}
+
+/// Walks east to the door and uses it, returning the denials sent meanwhile.
+fn use_door(app: &mut App, reader: &mut ManualEventReader<WolfDoorDeniedEvent>) -> Vec<WolfDoorDeniedEvent> {
+    hold(app, &[KeyCode::KeyW]);
+    let mut denied = run_reading(app, reader, 60);
+    hold(app, &[KeyCode::Space]);
+    denied.extend(run_reading(app, reader, 1));
+    hold(app, &[]);
+    denied.extend(run_reading(app, reader, 40));
+    denied
+}
+
+#[test]
+fn locked_door() {
+    let mut app = app("locked.wolfmap", tick());
+    let door = entity_at(&mut app, UVec2::new(4, 1), "door");
+    let key = entity_at(&mut app, UVec2::new(1, 1), "key");
+    let (e, _) = player(&mut app);
+    let mut denied_reader = ManualEventReader::<WolfDoorDeniedEvent>::default();
+    let mut pickup_reader = ManualEventReader::<WolfKeyPickupEvent>::default();
+
+    // the door stays closed without the key
+    let denied = use_door(&mut app, &mut denied_reader);
+    assert_eq!(denied.len(), 1);
+    assert_eq!((denied[0].door, denied[0].interactor, denied[0].lock.as_str()), (door, e, "gold"));
+    assert!(matches!(app.world.get::<WolfDoor>(door).unwrap().state, DoorState::Closed));
+
+    // walk back over the key
+    hold(&mut app, &[KeyCode::KeyS]);
+    let pickups = run_reading(&mut app, &mut pickup_reader, 60);
+    assert_eq!(pickups.len(), 1);
+    assert_eq!((pickups[0].entity, pickups[0].key.as_str()), (e, "gold"));
+    assert_eq!(app.world.get::<WolfInventory>(e).unwrap().keys, vec!["gold".to_string()]);
+    assert!(app.world.get_entity(key).is_none());
+
+    assert!(use_door(&mut app, &mut denied_reader).is_empty());
+    assert!(matches!(app.world.get::<WolfDoor>(door).unwrap().state, DoorState::Open { .. }));
+}
+
+#[test]
+fn push_wall() {
+    let mut app = app("push.wolfmap", tick());
+    let block = entity_at(&mut app, UVec2::new(2, 1), "block");
+
+    hold(&mut app, &[KeyCode::Space]);
+    run(&mut app, 1);
+    hold(&mut app, &[]);
+    // moves a tile per second until it hits the wall
+    run(&mut app, 300);
+    let pos = app.world.get::<Transform>(block).unwrap().translation;
+    assert!((pos.x - 5.5).abs() < 0.05 && pos.y == 1.5, "push wall at {} did not stop at the wall", pos);
+    assert!(app.world.get::<WolfPush>(block).is_none());
+
+    // walk into where the push wall was
+    hold(&mut app, &[KeyCode::KeyW]);
+    run(&mut app, 60);
+    let (_, pos) = player(&mut app);
+    assert!(pos.x > 4.0 && pos.x <= 4.6, "player at {} did not follow the push wall", pos);
+}
+
+#[test]
+fn collision() {
+    let mut app = app("collision.wolfmap", tick());
+    let barrel = entity_at(&mut app, UVec2::new(1, 1), "sprite");
+    let wall = entity_at(&mut app, UVec2::new(5, 2), "block");
+
+    // strafe south into the barrel
+    hold(&mut app, &[KeyCode::KeyD]);
+    let collisions = run(&mut app, 60);
+    let (e, pos) = player(&mut app);
+    assert!((pos.x - 1.5).abs() < 1e-3 && pos.y > 2.25 && pos.y <= 2.31, "player at {} did not stop at the barrel", pos);
+    assert_eq!(app.world.get::<Transform>(barrel).unwrap().translation, Vec3::new(1.5, 1.5, 0.5));
+    let hit = collisions.iter().find(|c| c.a == e && c.b == barrel).expect("no collision with the barrel");
+    assert!(hit.normal.distance(Vec2::NEG_Y) < 1e-3);
+    assert!(hit.depth > 0.0, "the player kept moving into the barrel, but the collision had no depth");
+
+    // walk east into the wall
+    hold(&mut app, &[KeyCode::KeyW]);
+    let collisions = run(&mut app, 60);
+    let (_, pos) = player(&mut app);
+    assert!(pos.x > 4.5 && pos.x <= 4.6, "player at {} did not stop at the wall", pos);
+    let hit = collisions.iter().find(|c| c.a == e && c.b == wall).expect("no collision with the wall");
+    assert!(hit.normal.distance(Vec2::X) < 1e-3);
+    assert!(hit.depth > 0.0, "the player kept moving into the wall, but the collision had no depth");
+}
+
+#[test]
+fn collision_groups() {
+    let mut app = app("groups.wolfmap", tick());
+    let barrel = entity_at(&mut app, UVec2::new(1, 1), "sprite");
+
+    // the barrel collides with no group, so the player strafes through it into the wall
+    hold(&mut app, &[KeyCode::KeyD]);
+    let collisions = run(&mut app, 60);
+    let (e, pos) = player(&mut app);
+    assert!(pos.y < 1.5, "player at {} was stopped by the barrel", pos);
+    assert!(!collisions.iter().any(|c| c.a == e && c.b == barrel), "the player collided with the barrel");
+    assert_eq!(app.world.get::<Transform>(barrel).unwrap().translation, Vec3::new(1.5, 1.5, 0.5));
+}
+
+#[test]
+fn trigger() {
+    let mut app = app("trigger.wolfmap", tick());
+    let trigger = entity_at(&mut app, UVec2::new(3, 1), "trigger");
+    let (e, _) = player(&mut app);
+
+    // walk east through the trigger into the wall
+    let mut enter_reader = ManualEventReader::<WolfTriggerEnter>::default();
+    let mut exit_reader = ManualEventReader::<WolfTriggerExit>::default();
+    let (mut enters, mut exits) = (Vec::new(), Vec::new());
+    hold(&mut app, &[KeyCode::KeyW]);
+    for _ in 0..60 {
+        app.update();
+        enters.extend(enter_reader.read(app.world.resource::<Events<WolfTriggerEnter>>()).map(|ev| (ev.trigger, ev.entity)));
+        exits.extend(exit_reader.read(app.world.resource::<Events<WolfTriggerExit>>()).map(|ev| (ev.trigger, ev.entity)));
+    }
+    let (_, pos) = player(&mut app);
+    assert!(pos.x > 4.5, "player at {} did not walk past the trigger", pos);
+    assert_eq!(enters, vec![(trigger, e)]);
+    assert_eq!(exits, vec![(trigger, e)]);
+    assert!(app.world.get::<WolfTrigger>(trigger).unwrap().inside().is_empty());
+}
+
+#[test]
+fn map_settings() {
+    let mut app = app("config.wolfmap", tick());
+    let config = WolfConfig::default();
+
+    // the map sets the open time, one door its own timing
+    let door = |app: &mut App, x| {
+        let e = entity_at(app, UVec2::new(x, 1), "door");
+        let door = app.world.get::<WolfDoor>(e).unwrap();
+        (door.open_time, door.auto_close)
+    };
+    assert_eq!(door(&mut app, 1), (2.0, config.door_auto_close));
+    assert_eq!(door(&mut app, 2), (0.25, None));
+
+    // the block, then the push marker and then the map
+    let push = |app: &mut App, x| {
+        let e = entity_at(app, UVec2::new(x, 1), "block");
+        let push = app.world.get::<WolfPush>(e).unwrap();
+        (push.speed, push.distance)
+    };
+    assert_eq!(push(&mut app, 3), (2.0, Some(1.0)));
+    assert_eq!(push(&mut app, 4), (3.0, Some(2.0)));
+    assert_eq!(push(&mut app, 5), (5.0, None));
+}
+
+#[test]
+fn hot_reload() {
+    let mut app = app("door.wolfmap", tick());
+    let door = entity_at(&mut app, UVec2::new(3, 1), "door");
+    let kept_wall = entity_at(&mut app, UVec2::new(1, 0), "block");
+    let removed_wall = entity_at(&mut app, UVec2::new(0, 0), "block");
+    hold(&mut app, &[KeyCode::KeyW]);
+    run(&mut app, 60);
+    hold(&mut app, &[KeyCode::Space]);
+    run(&mut app, 1);
+    hold(&mut app, &[]);
+    run(&mut app, 40);
+    let (player_e, pos) = player(&mut app);
+
+    // a wall moved from the corner into the corridor
+    let changed = parse_text_map(
+        std::path::Path::new("door.wolfmap"),
+        "[legend]\n\
+         # = block body\n\
+         D = door body interact\n\
+         P = player body; body_shape = ball; body_radius = 0.4\n\
+         [grid]\n\
+         #######\n\
+         #P.D.##\n\
+         .######\n",
+    )
+    .unwrap();
+    let handle = app.world.resource::<AssetServer>().get_handle::<WolfMap>("door.wolfmap").unwrap();
+    *app.world.resource_mut::<Assets<WolfMap>>().get_mut(&handle).unwrap() = changed;
+    app.world.send_event(AssetEvent::Modified { id: handle.id() });
+    run(&mut app, 2);
+
+    assert_eq!(player(&mut app), (player_e, pos), "the player was reset");
+    assert!(matches!(app.world.get::<WolfDoor>(door).unwrap().state, DoorState::Open { .. }));
+    assert_eq!(entity_at(&mut app, UVec2::new(1, 0), "block"), kept_wall);
+    assert!(app.world.get_entity(removed_wall).is_none());
+    let added_wall = entity_at(&mut app, UVec2::new(5, 1), "block");
+    assert!(app.world.get::<WolfStatic>(added_wall).is_some());
+    assert!(app.world.resource::<WolfWorld>().tiles.get(IVec2::new(5, 1)).is_some());
+    assert!(app.world.resource::<WolfWorld>().tiles.get(IVec2::new(0, 0)).is_none());
+}
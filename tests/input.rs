@@ -0,0 +1,58 @@
+mod common;
+
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent, GamepadInfo},
+    prelude::*,
+};
+use bevy_wolf::*;
+use common::*;
+
+fn pressed(app: &App, player: usize, action: WolfAction) -> bool {
+    app.world.resource::<WolfInput>().player(player).is_some_and(|actions| actions.pressed(action))
+}
+
+#[test]
+fn unbind_releases() {
+    let mut app = app("door.wolfmap", tick());
+    hold(&mut app, &[KeyCode::Space]);
+    app.update();
+    assert!(pressed(&app, 0, WolfAction::Use));
+
+    // still holding the key after it was unbound
+    app.world.resource_mut::<WolfInputMap>().unbind_action(WolfAction::Use);
+    app.update();
+    assert!(!pressed(&app, 0, WolfAction::Use));
+
+    app.world.resource_mut::<WolfInputMap>().rebind(WolfAction::Use, WolfInputSource::Key(KeyCode::KeyF), 1.0);
+    app.update();
+    assert!(!pressed(&app, 0, WolfAction::Use));
+    hold(&mut app, &[KeyCode::KeyF]);
+    app.update();
+    assert!(pressed(&app, 0, WolfAction::Use));
+}
+
+#[test]
+fn gamepads_in_order() {
+    let mut app = app("door.wolfmap", tick());
+    let ids = [7, 2, 11, 5, 0, 9];
+    for id in ids {
+        app.world.send_event(GamepadConnectionEvent::new(
+            Gamepad::new(id),
+            GamepadConnection::Connected(GamepadInfo { name: format!("gamepad {}", id) }),
+        ));
+    }
+    app.update();
+
+    let mut sorted = ids;
+    sorted.sort();
+    for (player, id) in sorted.into_iter().enumerate() {
+        let button = GamepadButton::new(Gamepad::new(id), GamepadButtonType::South);
+        app.world.resource_mut::<ButtonInput<GamepadButton>>().press(button);
+        app.update();
+        for other in 0..ids.len() {
+            assert_eq!(pressed(&app, other, WolfAction::Use), other == player, "gamepad {} pressed for player {}", id, other);
+        }
+        app.world.resource_mut::<ButtonInput<GamepadButton>>().release(button);
+        app.update();
+    }
+}